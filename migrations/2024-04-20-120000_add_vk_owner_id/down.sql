@@ -0,0 +1 @@
+ALTER TABLE channels DROP vk_owner_id;
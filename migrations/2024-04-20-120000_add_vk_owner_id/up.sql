@@ -0,0 +1,2 @@
+-- Числовой идентификатор владельца стены ВК.
+ALTER TABLE channels ADD vk_owner_id BIGINT;
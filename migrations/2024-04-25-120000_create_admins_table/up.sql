@@ -0,0 +1,10 @@
+CREATE TABLE admins (
+    -- Идентификатор пользователя Telegram, которому выдан доступ к боту.
+    user_id BIGINT PRIMARY KEY NOT NULL,
+
+    -- Идентификатор администратора из конфига, выдавшего доступ.
+    granted_by BIGINT NOT NULL,
+
+    -- Время выдачи доступа.
+    granted_timestamp BIGINT NOT NULL
+);
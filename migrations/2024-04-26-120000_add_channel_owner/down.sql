@@ -0,0 +1 @@
+ALTER TABLE channels DROP owner_user_id;
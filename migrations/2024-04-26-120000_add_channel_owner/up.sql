@@ -0,0 +1,2 @@
+-- Идентификатор пользователя Telegram, добавившего канал.
+ALTER TABLE channels ADD owner_user_id BIGINT;
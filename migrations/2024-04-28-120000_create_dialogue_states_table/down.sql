@@ -0,0 +1 @@
+DROP TABLE dialogue_states;
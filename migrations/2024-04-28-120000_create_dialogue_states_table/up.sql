@@ -0,0 +1,10 @@
+CREATE TABLE dialogue_states (
+    -- Идентификатор чата с пользователем.
+    chat_id BIGINT PRIMARY KEY NOT NULL,
+
+    -- Состояние диалога в JSON.
+    state TEXT NOT NULL,
+
+    -- Время последнего изменения состояния.
+    updated_timestamp BIGINT NOT NULL
+);
@@ -0,0 +1,18 @@
+CREATE TABLE forwarded_posts (
+    -- Идентификатор записи в таблице channels.
+    channel_id INTEGER NOT NULL,
+
+    -- Идентификатор владельца стены ВК.
+    vk_owner_id BIGINT NOT NULL,
+
+    -- Идентификатор публикации на стене ВК.
+    vk_post_id BIGINT NOT NULL,
+
+    -- Время публикации записи во ВКонтакте.
+    vk_post_timestamp BIGINT NOT NULL,
+
+    -- Время пересылки записи в Telegram.
+    forwarded_timestamp BIGINT NOT NULL,
+
+    PRIMARY KEY (channel_id, vk_owner_id, vk_post_id)
+);
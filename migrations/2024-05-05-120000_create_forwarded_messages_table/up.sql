@@ -0,0 +1,23 @@
+CREATE TABLE forwarded_messages (
+    -- Идентификатор Telegram канала.
+    tg_chat_id BIGINT NOT NULL,
+
+    -- Идентификатор сообщения в канале.
+    tg_message_id INTEGER NOT NULL,
+
+    -- Идентификатор записи в таблице channels.
+    channel_id INTEGER NOT NULL,
+
+    -- Идентификатор владельца стены ВК.
+    vk_owner_id BIGINT NOT NULL,
+
+    -- Идентификатор публикации на стене ВК.
+    vk_post_id BIGINT NOT NULL,
+
+    -- Порядковый номер сообщения среди сообщений публикации.
+    position INTEGER NOT NULL,
+
+    PRIMARY KEY (tg_chat_id, tg_message_id)
+);
+
+CREATE INDEX forwarded_messages_post ON forwarded_messages (channel_id, vk_owner_id, vk_post_id);
@@ -0,0 +1,3 @@
+ALTER TABLE forwarded_posts DROP is_sent;
+ALTER TABLE forwarded_posts DROP sent_steps;
+ALTER TABLE channels DROP partial_post_policy;
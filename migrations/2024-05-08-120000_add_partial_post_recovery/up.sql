@@ -0,0 +1,8 @@
+-- Что делать с публикацией, отправка которой прервалась: 'complete' или 'restart'.
+ALTER TABLE channels ADD partial_post_policy TEXT NOT NULL DEFAULT 'complete';
+
+-- Количество успешно выполненных шагов отправки публикации.
+ALTER TABLE forwarded_posts ADD sent_steps INTEGER NOT NULL DEFAULT 0;
+
+-- Публикация отправлена целиком.
+ALTER TABLE forwarded_posts ADD is_sent BOOLEAN NOT NULL DEFAULT TRUE;
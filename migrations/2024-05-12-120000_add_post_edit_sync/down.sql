@@ -0,0 +1,3 @@
+ALTER TABLE forwarded_messages DROP media_id;
+ALTER TABLE forwarded_posts DROP content_hash;
+ALTER TABLE channels DROP edit_window_secs;
@@ -0,0 +1,8 @@
+-- Сколько секунд после публикации отслеживаются её правки во ВКонтакте. 0 - не отслеживаются.
+ALTER TABLE channels ADD edit_window_secs INTEGER NOT NULL DEFAULT 86400;
+
+-- Хэш текста и вложений публикации на момент последней отправки или правки.
+ALTER TABLE forwarded_posts ADD content_hash TEXT;
+
+-- Идентификатор вложения ВК, отправленного этим сообщением.
+ALTER TABLE forwarded_messages ADD media_id TEXT;
@@ -0,0 +1,3 @@
+ALTER TABLE forwarded_messages DROP text;
+ALTER TABLE forwarded_posts DROP is_deleted;
+ALTER TABLE channels DROP deletion_mode;
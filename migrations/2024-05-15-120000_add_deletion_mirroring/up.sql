@@ -0,0 +1,8 @@
+-- Что делать с сообщениями, если публикация удалена во ВКонтакте: 'off', 'delete' или 'mark'.
+ALTER TABLE channels ADD deletion_mode TEXT NOT NULL DEFAULT 'off';
+
+-- Публикация удалена во ВКонтакте, и удаление перенесено в Telegram.
+ALTER TABLE forwarded_posts ADD is_deleted BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Текст или подпись, с которыми было отправлено сообщение.
+ALTER TABLE forwarded_messages ADD text TEXT;
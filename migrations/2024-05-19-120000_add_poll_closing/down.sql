@@ -0,0 +1 @@
+ALTER TABLE forwarded_messages DROP poll_close_timestamp;
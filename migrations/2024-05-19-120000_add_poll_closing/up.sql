@@ -0,0 +1,2 @@
+-- Время, когда опрос в этом сообщении нужно закрыть вслед за опросом во ВКонтакте.
+ALTER TABLE forwarded_messages ADD poll_close_timestamp BIGINT;
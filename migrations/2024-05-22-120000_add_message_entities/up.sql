@@ -0,0 +1,3 @@
+-- Разметка текста сообщения в виде сущностей Telegram, сериализованная в JSON.
+-- Отсутствует у вложений и у сообщений, отправленных в MarkdownV2.
+ALTER TABLE forwarded_messages ADD entities TEXT;
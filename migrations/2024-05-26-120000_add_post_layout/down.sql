@@ -0,0 +1,2 @@
+ALTER TABLE forwarded_posts DROP post_layout;
+ALTER TABLE channels DROP post_layout;
@@ -0,0 +1,6 @@
+-- Расположение текста публикации относительно вложений: 'separate' или 'compact'.
+ALTER TABLE channels ADD post_layout TEXT NOT NULL DEFAULT 'separate';
+
+-- Расположение, с которым публикация отправлялась. Нужно, чтобы дослать
+-- или отредактировать её теми же шагами после смены настройки.
+ALTER TABLE forwarded_posts ADD post_layout TEXT NOT NULL DEFAULT 'separate';
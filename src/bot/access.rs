@@ -0,0 +1,75 @@
+use crate::db::Db;
+use std::sync::Arc;
+use teloxide::types::UserId;
+
+/// Роль пользователя, которому разрешено пользоваться ботом.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Администратор из конфига. Может выдавать и отзывать доступ.
+    GlobalAdmin,
+
+    /// Пользователь, которому доступ выдан командой `/grant`.
+    Admin,
+}
+
+/// Пользователь с доступом к боту, от которого пришло обновление.
+#[derive(Clone, Copy, Debug)]
+pub struct Caller {
+    pub id: UserId,
+    pub role: Role,
+}
+
+impl Caller {
+    /// Возвращает пользователя, чьи каналы доступны вызывающему,
+    /// или `None`, если ему доступны все каналы.
+    pub fn channels_owner(&self) -> Option<UserId> {
+        match self.role {
+            Role::GlobalAdmin => None,
+            Role::Admin => Some(self.id),
+        }
+    }
+}
+
+/// Проверка доступа к командам бота.
+#[derive(Clone)]
+pub struct Access {
+    global_admins: Arc<[UserId]>,
+    db: Db,
+}
+
+impl Access {
+    pub fn new(global_admins: &[UserId], db: Db) -> Self {
+        Self {
+            global_admins: global_admins.into(),
+            db,
+        }
+    }
+
+    /// Возвращает роль пользователя или `None`, если доступа к боту у него нет.
+    pub async fn role(&self, user_id: UserId) -> Option<Role> {
+        if self.is_global_admin(user_id) {
+            return Some(Role::GlobalAdmin);
+        }
+
+        self.db.is_admin(user_id).await.then_some(Role::Admin)
+    }
+
+    pub fn is_global_admin(&self, user_id: UserId) -> bool {
+        self.global_admins.contains(&user_id)
+    }
+
+    /// Выдаёт доступ. Возвращает `false`, если доступ уже был.
+    pub async fn grant(&self, user_id: UserId, granted_by: UserId) -> bool {
+        self.db.add_admin(user_id, granted_by).await
+    }
+
+    /// Отзывает доступ. Возвращает `false`, если доступа не было.
+    pub async fn revoke(&self, user_id: UserId) -> bool {
+        self.db.remove_admin(user_id).await
+    }
+
+    /// Возвращает пользователей, которым доступ выдан командой `/grant`.
+    pub async fn granted(&self) -> Vec<UserId> {
+        self.db.get_admins().await
+    }
+}
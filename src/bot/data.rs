@@ -1,7 +1,10 @@
-use crate::domain::{ChannelEntryId, ChannelInfo, TelegramChannelId, VkId};
+use crate::domain::{
+    ChannelEntryId, ChannelInfo, ChannelSettings, DeletionMode, PartialPostPolicy, PostLayout,
+    TelegramChannelId, VkId, VkPostId,
+};
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
-use teloxide::types::ChatId;
+use std::collections::BTreeMap;
+use teloxide::types::{ChatId, UserId};
 
 pub const START_MESSAGE: &str = "\
 Добро пожаловать в бот для пересылки постов из ВК в Телеграм!
@@ -12,6 +15,10 @@ pub const START_MESSAGE: &str = "\
 
 👀 Для просмотра всех каналов, существует команда /list.
 
+⚙️ Настроить пересылку в канал можно командой /settings.
+
+🔗 Чтобы узнать, из какой публикации ВК пришло сообщение в канале, используйте команду /source.
+
 🛑 Если вы хотите отменить добавление или удаление, используйте команду /cancel.
 
 Приятного использования!";
@@ -25,32 +32,130 @@ pub const HELP_MESSAGE: &str = "\
 
 • 👀 Список всех каналов: /list
 
+• ⚙️ Настройки канала: /settings
+
+• 🔗 Источник сообщения в канале: /source
+
 • 🛑 Отмена действия: /cancel";
 
+pub const GLOBAL_ADMIN_HELP_MESSAGE: &str = "\
+Управление доступом:
+
+• ✅ Выдать доступ: /grant <id пользователя>
+
+• ⛔ Отозвать доступ: /revoke <id пользователя>
+
+• 👥 Список пользователей с доступом: /admins";
+
+pub const ACCESS_DENIED_MESSAGE: &dyn Fn(&UserId) -> String = &|id| {
+    format!("⛔ У вас нет доступа к этому боту. Чтобы его получить, передайте администратору ваш идентификатор: {id}")
+};
+
+pub const NOT_ENOUGH_RIGHTS_MESSAGE: &str =
+    "⛔ Эта команда доступна только администраторам из конфигурации бота";
+
+pub const GRANT_USAGE_MESSAGE: &str =
+    "Напишите, пожалуйста, идентификатор пользователя после команды, например: /grant 123456789";
+
+pub const REVOKE_USAGE_MESSAGE: &str =
+    "Напишите, пожалуйста, идентификатор пользователя после команды, например: /revoke 123456789";
+
+pub const ACCESS_GRANTED_MESSAGE: &dyn Fn(&UserId) -> String =
+    &|id| format!("✅ Пользователь {id} получил доступ к боту");
+
+pub const ACCESS_ALREADY_GRANTED_MESSAGE: &dyn Fn(&UserId) -> String =
+    &|id| format!("У пользователя {id} уже есть доступ к боту");
+
+pub const ACCESS_REVOKED_MESSAGE: &dyn Fn(&UserId) -> String =
+    &|id| format!("⛔ Пользователь {id} больше не имеет доступа к боту");
+
+pub const ACCESS_NOT_GRANTED_MESSAGE: &dyn Fn(&UserId) -> String =
+    &|id| format!("У пользователя {id} нет доступа к боту");
+
+pub const CANNOT_REVOKE_GLOBAL_ADMIN_MESSAGE: &str =
+    "Доступ администраторов из конфигурации бота нельзя отозвать командой";
+
+pub const NO_ADMINS_MESSAGE: &str = "👥 Доступ к боту пока никому не выдан";
+
+pub const LIST_ADMINS_MESSAGE: &dyn Fn(&[UserId]) -> String = &|admins| {
+    format!(
+        "👥 Пользователи с доступом к боту:\n\n{}",
+        admins
+            .iter()
+            .enumerate()
+            .map(|(n, id)| format!("{n}. {id}", n = n + 1))
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+};
+
 pub const REQUEST_CHANNEL_MESSAGE: &str =
     "↪ Перешли, пожалуйста, сообщение из канала, в который будут отправляться посты из ВК";
 
 pub const INVALID_CHANNEL_MESSAGE: &str = "Сообщение должно быть переслано из канала";
 
+pub const BOT_IS_NOT_ADMIN_MESSAGE: &str =
+    "🤖 Бот не администратор этого канала. Добавьте его в администраторы и перешлите сообщение ещё раз";
+
+pub const BOT_CANNOT_POST_MESSAGE: &str =
+    "✍️ У бота нет права публиковать сообщения в канале. Выдайте его и перешлите сообщение ещё раз";
+
+pub const BOT_CANNOT_EDIT_MESSAGE: &str =
+    "📌 У бота нет права редактировать сообщения в канале, без него нельзя закреплять посты. \
+     Выдайте его и перешлите сообщение ещё раз";
+
+pub const USER_IS_NOT_ADMIN_MESSAGE: &str =
+    "⛔ Вы не администратор этого канала. Добавлять можно только свои каналы";
+
+pub const PERMISSIONS_CHECK_FAILED_MESSAGE: &str =
+    "😔 Не получилось проверить права в канале. Попробуйте, пожалуйста, ещё раз позже";
+
 pub const CHANNEL_RECEIVED_MESSAGE: &dyn Fn(&ChatId, &str) -> String =
     &|id, title| format!("Посты будут публиковаться в канал '{title}' ({id})");
 
 pub const REQUEST_VK_URL_MESSAGE: &str =
     "Напишите, пожалуйста, ссылку на стену сообщества, группы или человека во ВКонтакте";
 
+pub const VK_WALL_NOT_FOUND_MESSAGE: &str =
+    "🔍 Не нашёл такого сообщества или человека во ВКонтакте. Проверьте, пожалуйста, ссылку";
+
+pub const VK_WALL_RESOLVE_FAILED_MESSAGE: &str =
+    "😔 Не получилось проверить ссылку во ВКонтакте. Попробуйте, пожалуйста, ещё раз позже";
+
+pub const VK_WALL_DELETED_MESSAGE: &str =
+    "🗑️ Эта страница или сообщество удалены во ВКонтакте. Пришлите, пожалуйста, другую ссылку";
+
+pub const VK_WALL_BLOCKED_MESSAGE: &str =
+    "🚫 Эта страница или сообщество заблокированы во ВКонтакте. Пришлите, пожалуйста, другую ссылку";
+
+pub const VK_WALL_PRIVATE_MESSAGE: &str =
+    "🔒 Стена закрыта, и бот не может читать публикации. Пришлите, пожалуйста, ссылку на открытую стену";
+
+pub const APPROVE_VK_WALL_MESSAGE: &dyn Fn(&str, &VkId) -> String =
+    &|name, vk_id| format!("Посты будут пересылаться со стены «{name}» ({vk_id}). Всё верно?");
+
+#[rustfmt::skip]
+pub static APPROVE_VK_WALL_BUTTONS: Lazy<[(&str, (usize, &str)); 2]> = Lazy::new(|| {
+    [
+        ("true", (0, "✅ Да")),
+        ("false", (0, "Другая ссылка")),
+    ]
+});
+
 pub const CHANNEL_ADDED_MESSAGE: &dyn Fn(&VkId, &TelegramChannelId) -> String =
     &|vk_id, tg_id| format!("✉️ Всё настроено! Посты будут репоститься из {vk_id} в {tg_id}");
 
 pub const NO_CHANNELS_MESSAGE: &str =
     "🥺 У вас нет каналов. Чтобы настроить пересылку из ВК в Telegram используйте команду /add";
 
-pub const REQUEST_CHANNEL_NUMBER_MESSAGE: &dyn Fn(&HashMap<ChannelEntryId, ChannelInfo>) -> String =
-    &|channels| {
-        format!(
-            "📋 Отправьте номер записи, которую хотите удалить:\n\n{}",
-            format_channels_to_string(channels)
-        )
-    };
+pub const REQUEST_CHANNEL_NUMBER_MESSAGE: &dyn Fn(
+    &BTreeMap<ChannelEntryId, ChannelInfo>,
+) -> String = &|channels| {
+    format!(
+        "📋 Отправьте номер записи, которую хотите удалить:\n\n{}",
+        format_channels_to_string(channels)
+    )
+};
 
 pub const INVALID_CHANNEL_NUMBER_MESSAGE: &str = "Напишите, пожалуйста, номер из списка";
 
@@ -74,7 +179,7 @@ pub const CHANNEL_DELETED_MESSAGE: &dyn Fn(&VkId, &TelegramChannelId) -> String
 
 pub const CHANNEL_DELETION_CANCELLED_MESSAGE: &str = "Удаление отменено";
 
-pub const LIST_CHANNELS_MESSAGE: &dyn Fn(&HashMap<ChannelEntryId, ChannelInfo>) -> String =
+pub const LIST_CHANNELS_MESSAGE: &dyn Fn(&BTreeMap<ChannelEntryId, ChannelInfo>) -> String =
     &|channels| {
         format!(
             "📋 Ваш список каналов:\n\n{}",
@@ -82,12 +187,120 @@ pub const LIST_CHANNELS_MESSAGE: &dyn Fn(&HashMap<ChannelEntryId, ChannelInfo>)
         )
     };
 
+pub const REQUEST_CHANNEL_NUMBER_FOR_SETTINGS_MESSAGE: &dyn Fn(
+    &BTreeMap<ChannelEntryId, ChannelInfo>,
+) -> String = &|channels| {
+    format!(
+        "📋 Отправьте номер записи, которую хотите настроить:\n\n{}",
+        format_channels_to_string(channels)
+    )
+};
+
+pub const CHANNEL_SETTINGS_MESSAGE: &dyn Fn(&ChannelInfo) -> String = &|info| {
+    let partial_post_policy = match info.settings.partial_post_policy {
+        PartialPostPolicy::Complete => "дослать недостающие части",
+        PartialPostPolicy::Restart => "удалить отправленное и отправить заново",
+    };
+
+    let edit_window = match info.settings.edit_window.num_hours() {
+        0 => "не отслеживать".to_owned(),
+        hours @ 1..=23 => format!("в течение {hours} ч. после публикации"),
+        hours => format!("в течение {} дн. после публикации", hours / 24),
+    };
+
+    let deletion_mode = match info.settings.deletion_mode {
+        DeletionMode::Off => "ничего не делать",
+        DeletionMode::Delete => "удалить из канала",
+        DeletionMode::Mark => "пометить в канале как удалённый",
+    };
+
+    let post_layout = match info.settings.post_layout {
+        PostLayout::Separate => "текст отдельным сообщением",
+        PostLayout::Compact => "короткий текст в подписи к первому вложению",
+    };
+
+    format!(
+        "⚙️ Настройки пересылки из {vk_id} в {tg_id}\n\n\
+         • Если отправка поста прервалась: {partial_post_policy}\n\
+         • Правки и удаление постов во ВКонтакте: {edit_window}\n\
+         • Если пост удалён во ВКонтакте: {deletion_mode}\n\
+         • Вид постов с вложениями: {post_layout}\n\n\
+         Нажмите на настройку, чтобы изменить её",
+        vk_id = info.vk_public_id,
+        tg_id = info.tg_channel,
+    )
+};
+
+pub const SETTING_PARTIAL_POST_POLICY: &str = "partial_post_policy";
+
+pub const SETTING_EDIT_WINDOW: &str = "edit_window";
+
+pub const SETTING_DELETION_MODE: &str = "deletion_mode";
+
+pub const SETTING_POST_LAYOUT: &str = "post_layout";
+
+pub const SETTINGS_DONE: &str = "done";
+
+pub type Buttons = Vec<(&'static str, (usize, &'static str))>;
+
+pub const CHANNEL_SETTINGS_BUTTONS: &dyn Fn(&ChannelSettings) -> Buttons = &|settings| {
+    let partial_post_policy = match settings.partial_post_policy {
+        PartialPostPolicy::Complete => "Прерванные посты: досылать",
+        PartialPostPolicy::Restart => "Прерванные посты: отправлять заново",
+    };
+
+    let edit_window = match settings.edit_window.num_hours() {
+        0 => "Отслеживание: выключено",
+        1 => "Отслеживание: 1 час",
+        24 => "Отслеживание: 1 день",
+        72 => "Отслеживание: 3 дня",
+        168 => "Отслеживание: 7 дней",
+        _ => "Отслеживание: другое окно",
+    };
+
+    let deletion_mode = match settings.deletion_mode {
+        DeletionMode::Off => "Удалённые посты: не трогать",
+        DeletionMode::Delete => "Удалённые посты: удалять",
+        DeletionMode::Mark => "Удалённые посты: помечать",
+    };
+
+    let post_layout = match settings.post_layout {
+        PostLayout::Separate => "Вид постов: текст отдельно",
+        PostLayout::Compact => "Вид постов: компактный",
+    };
+
+    vec![
+        (SETTING_PARTIAL_POST_POLICY, (0, partial_post_policy)),
+        (SETTING_EDIT_WINDOW, (1, edit_window)),
+        (SETTING_DELETION_MODE, (2, deletion_mode)),
+        (SETTING_POST_LAYOUT, (3, post_layout)),
+        (SETTINGS_DONE, (4, "✅ Готово")),
+    ]
+};
+
+pub const CHANNEL_NOT_FOUND_MESSAGE: &str = "🔍 Канал не найден. Возможно, он уже удалён";
+
+pub const REQUEST_FORWARDED_MESSAGE_MESSAGE: &str =
+    "↪ Перешли, пожалуйста, сообщение из канала, и я найду публикацию ВК, из которой оно пришло";
+
+pub const POST_SOURCE_MESSAGE: &dyn Fn(&VkPostId) -> String =
+    &|post| format!("🔗 Сообщение переслано из публикации {post}");
+
+pub const POST_SOURCE_NOT_FOUND_MESSAGE: &str =
+    "🔍 Не нашёл публикацию ВК для этого сообщения. Возможно, оно отправлено не ботом или не из ваших каналов";
+
+/// Пометка в канале для публикаций, удалённых во ВКонтакте.
+pub const POST_DELETED_MARKER: &str = "🗑️ Пост удалён во ВКонтакте";
+
+/// Та же пометка для сообщений в MarkdownV2.
+pub const POST_DELETED_MARKDOWN_MARKER: &str = "🗑️ _Пост удалён во ВКонтакте_";
+
 pub const CANCEL_MESSAGE: &str = "Команда отменена";
 
 pub const UNKNOWN_ACTION_MESSAGE: &str =
     "😔 Я вас не понимаю. Воспользуйтесь командой /help для получения помощи";
 
-fn format_channels_to_string(channels: &HashMap<ChannelEntryId, ChannelInfo>) -> String {
+fn format_channels_to_string(channels: &BTreeMap<ChannelEntryId, ChannelInfo>) -> String {
     channels
         .iter()
         .enumerate()
@@ -1,21 +1,21 @@
-use super::{data::*, utils::*};
+use super::{access::*, data::*, permissions::*, storage::DbStorage, utils::*};
 use crate::{
-    domain::{ChannelEntryId, ChannelInfo, TelegramChannelId, VkId},
-    vk_poller,
+    domain::{
+        ChannelEntryId, ChannelInfo, ChannelSettings, DeletionMode, PartialPostPolicy, PostLayout,
+        TelegramChannelId, VkId, VkOwnerId, VkWallAddress,
+    },
+    vk_poller::{self, WallCheck},
 };
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
 use teloxide::{
-    dispatching::{
-        dialogue::{self, InMemStorage},
-        ShutdownToken, UpdateHandler,
-    },
+    dispatching::{dialogue, ShutdownToken, UpdateHandler},
     macros::BotCommands,
     prelude::*,
-    types::{Chat, ChatKind, ChatPublic, ForwardedFrom},
+    types::{Chat, ChatKind, ChatPublic, ForwardedFrom, MessageId, UserId},
 };
-use url::Url;
 
-pub(crate) type BotDialogue = Dialogue<BotState, InMemStorage<BotState>>;
+pub(crate) type BotDialogue = Dialogue<BotState, DbStorage>;
 pub(crate) type HandlerResult = Result<(), anyhow::Error>;
 pub(crate) type HandlerError = anyhow::Error;
 
@@ -27,27 +27,45 @@ enum BotCommand {
     Add,
     Delete,
     List,
+    Settings,
+    Source,
     Cancel,
+    Grant(String),
+    Revoke(String),
+    Admins,
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum BotState {
     #[default]
     Empty,
     AddingChanneд(AddingChannelBotState),
     DeletingChannel(DeletingChannelBotState),
+    ConfiguringChannel(ConfiguringChannelBotState),
+    FindingPostSource,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum AddingChannelBotState {
     ReceiveChannelId,
-    ReceiveVkUrl { channel_id: ChatId },
+    ReceiveVkUrl {
+        channel_id: ChatId,
+    },
+    ApproveVkWall {
+        channel_id: ChatId,
+        vk_id: VkId,
+        owner_id: VkOwnerId,
+        message: Message,
+    },
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum DeletingChannelBotState {
     ReceiveChannelNumber {
-        channels: HashMap<ChannelEntryId, ChannelInfo>,
+        channels: BTreeMap<ChannelEntryId, ChannelInfo>,
     },
     ApproveDelete {
         message: Message,
@@ -56,11 +74,27 @@ pub enum DeletingChannelBotState {
     },
 }
 
-pub async fn start(bot: Bot, poller: vk_poller::VkPollManager) -> ShutdownToken {
-    let bot_state = InMemStorage::<BotState>::new();
+#[derive(Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum ConfiguringChannelBotState {
+    ReceiveChannelNumber {
+        channels: BTreeMap<ChannelEntryId, ChannelInfo>,
+    },
+    ChangeSettings {
+        message: Message,
+        id: ChannelEntryId,
+        info: ChannelInfo,
+    },
+}
 
+pub async fn start(
+    bot: Bot,
+    poller: vk_poller::VkPollManager,
+    access: Access,
+    bot_state: Arc<DbStorage>,
+) -> ShutdownToken {
     let mut dispatcher = Dispatcher::builder(bot, schema())
-        .dependencies(dptree::deps![bot_state, poller])
+        .dependencies(dptree::deps![bot_state, poller, access])
         .build();
 
     let token = dispatcher.shutdown_token();
@@ -84,6 +118,11 @@ fn schema() -> UpdateHandler<HandlerError> {
                 .branch(case![BotCommand::Add].endpoint(add_channel))
                 .branch(case![BotCommand::Delete].endpoint(delete_channel))
                 .branch(case![BotCommand::List].endpoint(list_channels))
+                .branch(case![BotCommand::Settings].endpoint(configure_channel))
+                .branch(case![BotCommand::Source].endpoint(find_post_source))
+                .branch(case![BotCommand::Grant(user_id)].endpoint(grant_access))
+                .branch(case![BotCommand::Revoke(user_id)].endpoint(revoke_access))
+                .branch(case![BotCommand::Admins].endpoint(list_admins))
         )
         .branch(case![BotCommand::Cancel].endpoint(cancel_action)
     );
@@ -99,16 +138,58 @@ fn schema() -> UpdateHandler<HandlerError> {
             case![BotState::DeletingChannel(state)]
                 .branch(case![DeletingChannelBotState::ReceiveChannelNumber {channels }].endpoint(receive_entry_for_delete))
         )
+        .branch(
+            case![BotState::ConfiguringChannel(state)]
+                .branch(case![ConfiguringChannelBotState::ReceiveChannelNumber { channels }].endpoint(receive_entry_for_settings))
+        )
+        .branch(case![BotState::FindingPostSource].endpoint(receive_message_for_source))
         .branch(dptree::endpoint(other));
         
     let callback_query_handler = Update::filter_callback_query()
+        .branch(
+            case![BotState::AddingChanneд(state)]
+                .branch(case![AddingChannelBotState::ApproveVkWall { channel_id, vk_id, owner_id, message }].endpoint(approve_vk_wall))
+        )
         .branch(
             case![BotState::DeletingChannel(state)]
                 .branch(case![DeletingChannelBotState::ApproveDelete { message, id, info }].endpoint(approve_delete))
+        )
+        .branch(
+            case![BotState::ConfiguringChannel(state)]
+                .branch(case![ConfiguringChannelBotState::ChangeSettings { message, id, info }].endpoint(change_settings))
+        );
+
+    let authorized_handler = dptree::filter_map_async(authorize)
+        .chain(
+            dialogue::enter::<Update, DbStorage, BotState, _>()
+                .branch(message_handler)
+                .branch(callback_query_handler)
         );
 
-    dialogue::enter::<Update, InMemStorage<BotState>, BotState, _>().branch(message_handler)
-    .branch(callback_query_handler)
+    let unauthorized_handler = Update::filter_message().endpoint(access_denied);
+
+    dptree::entry()
+        .branch(authorized_handler)
+        .branch(unauthorized_handler)
+}
+
+/// Пропускает дальше только пользователей с доступом к боту, добавляя их в зависимости.
+async fn authorize(upd: Update, access: Access) -> Option<Caller> {
+    let id = upd.user()?.id;
+    let role = access.role(id).await?;
+
+    Some(Caller { id, role })
+}
+
+/// Ответ пользователям без доступа к боту.
+async fn access_denied(bot: Bot, msg: Message) -> HandlerResult {
+    let Some(user) = msg.from() else {
+        return Ok(());
+    };
+
+    log::info!("Access denied for user {id}", id = user.id);
+
+    send_msg(&bot, msg.chat.id, &ACCESS_DENIED_MESSAGE(&user.id)).await
 }
 
 /// Команда `/start`.
@@ -117,8 +198,14 @@ async fn hello(bot: Bot, dialogue: BotDialogue) -> HandlerResult {
 }
 
 /// Команда `/help`.
-async fn help(bot: Bot, dialogue: BotDialogue) -> HandlerResult {
-    send_msg(&bot, dialogue.chat_id(), HELP_MESSAGE).await
+async fn help(bot: Bot, dialogue: BotDialogue, caller: Caller) -> HandlerResult {
+    match caller.role {
+        Role::GlobalAdmin => {
+            let text = format!("{HELP_MESSAGE}\n\n{GLOBAL_ADMIN_HELP_MESSAGE}");
+            send_msg(&bot, dialogue.chat_id(), &text).await
+        }
+        Role::Admin => send_msg(&bot, dialogue.chat_id(), HELP_MESSAGE).await,
+    }
 }
 
 /// Команда `/add`.
@@ -130,7 +217,6 @@ async fn add_channel(bot: Bot, dialogue: BotDialogue) -> HandlerResult {
             AddingChannelBotState::ReceiveChannelId,
         ))
         .await
-        .map_err(Into::into)
 }
 
 async fn receive_channel_id(bot: Bot, dialogue: BotDialogue, msg: Message) -> HandlerResult {
@@ -151,6 +237,28 @@ async fn receive_channel_id(bot: Bot, dialogue: BotDialogue, msg: Message) -> Ha
         return send_msg(&bot, dialogue.chat_id(), INVALID_CHANNEL_MESSAGE).await;
     }
 
+    let Some(user) = msg.from() else {
+        return send_msg(&bot, dialogue.chat_id(), INVALID_CHANNEL_MESSAGE).await;
+    };
+
+    match check_channel_permissions(&bot, *channel_id, user.id).await {
+        Ok(None) => {}
+        Ok(Some(missing)) => {
+            let text = match missing {
+                MissingPermission::BotIsNotAdmin => BOT_IS_NOT_ADMIN_MESSAGE,
+                MissingPermission::BotCannotPost => BOT_CANNOT_POST_MESSAGE,
+                MissingPermission::BotCannotEdit => BOT_CANNOT_EDIT_MESSAGE,
+                MissingPermission::UserIsNotAdmin => USER_IS_NOT_ADMIN_MESSAGE,
+            };
+
+            return send_msg(&bot, dialogue.chat_id(), text).await;
+        }
+        Err(err) => {
+            log::warn!("Failed to check permissions in channel {channel_id}: {err:#}");
+            return send_msg(&bot, dialogue.chat_id(), PERMISSIONS_CHECK_FAILED_MESSAGE).await;
+        }
+    }
+
     send_msg(
         &bot,
         dialogue.chat_id(),
@@ -178,11 +286,96 @@ async fn receive_vk_url(
     msg: Message,
     poller: vk_poller::VkPollManager,
 ) -> HandlerResult {
-    let Some(url) = msg.text().and_then(|text| Url::parse(text).ok()) else {
+    let Some(address) = msg.text().and_then(VkWallAddress::parse) else {
         return send_msg(&bot, dialogue.chat_id(), REQUEST_VK_URL_MESSAGE).await;
     };
 
-    let vk_id = VkId::from(url);
+    let owner_id = match poller.resolve_wall(&address).await {
+        Ok(Some(owner_id)) => owner_id,
+        Ok(None) => {
+            return send_msg(&bot, dialogue.chat_id(), VK_WALL_NOT_FOUND_MESSAGE).await;
+        }
+        Err(err) => {
+            log::warn!("Failed to resolve VK wall address {address:?}: {err:#}");
+            return send_msg(&bot, dialogue.chat_id(), VK_WALL_RESOLVE_FAILED_MESSAGE).await;
+        }
+    };
+
+    let info = match poller.check_wall(owner_id).await {
+        Ok(WallCheck::Available(info)) => info,
+        Ok(WallCheck::NotFound) => {
+            return send_msg(&bot, dialogue.chat_id(), VK_WALL_NOT_FOUND_MESSAGE).await;
+        }
+        Ok(WallCheck::Deleted) => {
+            return send_msg(&bot, dialogue.chat_id(), VK_WALL_DELETED_MESSAGE).await;
+        }
+        Ok(WallCheck::Blocked) => {
+            return send_msg(&bot, dialogue.chat_id(), VK_WALL_BLOCKED_MESSAGE).await;
+        }
+        Ok(WallCheck::Private) => {
+            return send_msg(&bot, dialogue.chat_id(), VK_WALL_PRIVATE_MESSAGE).await;
+        }
+        Err(err) => {
+            log::warn!("Failed to check VK wall {address:?}: {err:#}");
+            return send_msg(&bot, dialogue.chat_id(), VK_WALL_RESOLVE_FAILED_MESSAGE).await;
+        }
+    };
+
+    let vk_id = info
+        .screen_name
+        .map(VkId)
+        .unwrap_or_else(|| address.to_vk_id());
+
+    let text = APPROVE_VK_WALL_MESSAGE(&info.name, &vk_id);
+
+    let message = match info.avatar {
+        Some(avatar) => {
+            send_interactive_photo(&bot, &dialogue, avatar, &text, &*APPROVE_VK_WALL_BUTTONS)
+                .await?
+        }
+        None => send_interative(&bot, &dialogue, &text, &*APPROVE_VK_WALL_BUTTONS).await?,
+    };
+
+    dialogue
+        .update(BotState::AddingChanneд(
+            AddingChannelBotState::ApproveVkWall {
+                channel_id: posts_channel_id,
+                vk_id,
+                owner_id,
+                message,
+            },
+        ))
+        .await?;
+
+    Ok(())
+}
+
+async fn approve_vk_wall(
+    bot: Bot,
+    dialogue: BotDialogue,
+    poller: vk_poller::VkPollManager,
+    caller: Caller,
+    q: CallbackQuery,
+    (posts_channel_id, vk_id, owner_id, message): (ChatId, VkId, VkOwnerId, Message),
+) -> HandlerResult {
+    let Some(approved) = q.data.as_deref().and_then(|data| data.parse::<bool>().ok()) else {
+        return ignore_stale_button(&bot, &q).await;
+    };
+
+    remove_buttons(&bot, &message).await?;
+
+    if !approved {
+        send_msg(&bot, dialogue.chat_id(), REQUEST_VK_URL_MESSAGE).await?;
+
+        return dialogue
+            .update(BotState::AddingChanneд(
+                AddingChannelBotState::ReceiveVkUrl {
+                    channel_id: posts_channel_id,
+                },
+            ))
+            .await;
+    }
+
     let tg_id = TelegramChannelId(posts_channel_id.0);
 
     send_msg(
@@ -196,9 +389,13 @@ async fn receive_vk_url(
         .create(ChannelInfo {
             tg_channel: tg_id,
             vk_public_id: vk_id,
+            vk_owner_id: Some(owner_id),
             poll_interval: chrono::Duration::seconds(2),
             last_poll_datetime: None,
+            last_post_id: None,
             last_post_datetime: None,
+            owner: Some(caller.id),
+            settings: ChannelSettings::default(),
         })
         .await;
 
@@ -212,8 +409,9 @@ async fn delete_channel(
     bot: Bot,
     dialogue: BotDialogue,
     poller: vk_poller::VkPollManager,
+    caller: Caller,
 ) -> HandlerResult {
-    let channels = poller.get_channels().await;
+    let channels = poller.get_channels(caller.channels_owner()).await;
 
     if channels.is_empty() {
         return send_msg(&bot, dialogue.chat_id(), NO_CHANNELS_MESSAGE).await;
@@ -239,7 +437,7 @@ async fn receive_entry_for_delete(
     bot: Bot,
     dialogue: BotDialogue,
     msg: Message,
-    channels: HashMap<ChannelEntryId, ChannelInfo>,
+    channels: BTreeMap<ChannelEntryId, ChannelInfo>,
 ) -> HandlerResult {
     let Some(text) = msg.text() else {
         return send_msg(&bot, dialogue.chat_id(), INVALID_CHANNEL_NUMBER_MESSAGE).await;
@@ -281,12 +479,8 @@ async fn approve_delete(
     q: CallbackQuery,
     (message, channel_id, channel_info): (Message, ChannelEntryId, ChannelInfo),
 ) -> HandlerResult {
-    let Some(approved) = q.data else {
-        return Ok(());
-    };
-
-    let Ok(approved) = approved.parse() else {
-        panic!("Invalid value '{approved}'");
+    let Some(approved) = q.data.as_deref().and_then(|data| data.parse::<bool>().ok()) else {
+        return ignore_stale_button(&bot, &q).await;
     };
 
     if approved {
@@ -295,8 +489,11 @@ async fn approve_delete(
 
         edit_msg(&bot, &message, &STOPPING_CHANNEL_JOB_MESSAGE(vk_id, tg_id)).await?;
 
-        let delete_success = poller.delete(channel_id).await;
-        assert!(delete_success);
+        // Канал мог быть удалён, пока ожидалось подтверждение.
+        if !poller.delete(channel_id).await {
+            edit_msg(&bot, &message, CHANNEL_NOT_FOUND_MESSAGE).await?;
+            return dialogue.update(BotState::Empty).await;
+        }
 
         edit_msg(&bot, &message, &CHANNEL_DELETED_MESSAGE(vk_id, tg_id)).await?;
     } else {
@@ -313,8 +510,9 @@ async fn list_channels(
     bot: Bot,
     dialogue: BotDialogue,
     poller: vk_poller::VkPollManager,
+    caller: Caller,
 ) -> HandlerResult {
-    let channels = poller.get_channels().await;
+    let channels = poller.get_channels(caller.channels_owner()).await;
 
     if channels.is_empty() {
         send_msg(&bot, dialogue.chat_id(), NO_CHANNELS_MESSAGE).await
@@ -323,6 +521,268 @@ async fn list_channels(
     }
 }
 
+/// Команда `/settings`.
+async fn configure_channel(
+    bot: Bot,
+    dialogue: BotDialogue,
+    poller: vk_poller::VkPollManager,
+    caller: Caller,
+) -> HandlerResult {
+    let channels = poller.get_channels(caller.channels_owner()).await;
+
+    if channels.is_empty() {
+        return send_msg(&bot, dialogue.chat_id(), NO_CHANNELS_MESSAGE).await;
+    }
+
+    send_msg(
+        &bot,
+        dialogue.chat_id(),
+        &REQUEST_CHANNEL_NUMBER_FOR_SETTINGS_MESSAGE(&channels),
+    )
+    .await?;
+
+    dialogue
+        .update(BotState::ConfiguringChannel(
+            ConfiguringChannelBotState::ReceiveChannelNumber { channels },
+        ))
+        .await
+}
+
+async fn receive_entry_for_settings(
+    bot: Bot,
+    dialogue: BotDialogue,
+    msg: Message,
+    channels: BTreeMap<ChannelEntryId, ChannelInfo>,
+) -> HandlerResult {
+    let Some(number) = msg
+        .text()
+        .and_then(|text| text.parse().ok())
+        .and_then(|number: usize| number.checked_sub(1))
+    else {
+        return send_msg(&bot, dialogue.chat_id(), INVALID_CHANNEL_NUMBER_MESSAGE).await;
+    };
+
+    let Some((id, info)) = channels.into_iter().nth(number) else {
+        return send_msg(&bot, dialogue.chat_id(), INVALID_CHANNEL_NUMBER_MESSAGE).await;
+    };
+
+    let message = send_interative(
+        &bot,
+        &dialogue,
+        &CHANNEL_SETTINGS_MESSAGE(&info),
+        &CHANNEL_SETTINGS_BUTTONS(&info.settings),
+    )
+    .await?;
+
+    dialogue
+        .update(BotState::ConfiguringChannel(
+            ConfiguringChannelBotState::ChangeSettings { message, id, info },
+        ))
+        .await
+}
+
+async fn change_settings(
+    bot: Bot,
+    dialogue: BotDialogue,
+    poller: vk_poller::VkPollManager,
+    q: CallbackQuery,
+    (message, id, mut info): (Message, ChannelEntryId, ChannelInfo),
+) -> HandlerResult {
+    let Some(setting) = q.data.as_deref() else {
+        return ignore_stale_button(&bot, &q).await;
+    };
+
+    match setting {
+        SETTINGS_DONE => {
+            remove_buttons(&bot, &message).await?;
+            return dialogue.update(BotState::Empty).await;
+        }
+        SETTING_PARTIAL_POST_POLICY => {
+            info.settings.partial_post_policy = match info.settings.partial_post_policy {
+                PartialPostPolicy::Complete => PartialPostPolicy::Restart,
+                PartialPostPolicy::Restart => PartialPostPolicy::Complete,
+            };
+        }
+        SETTING_EDIT_WINDOW => {
+            info.settings.edit_window = next_edit_window(info.settings.edit_window);
+        }
+        SETTING_DELETION_MODE => {
+            info.settings.deletion_mode = match info.settings.deletion_mode {
+                DeletionMode::Off => DeletionMode::Delete,
+                DeletionMode::Delete => DeletionMode::Mark,
+                DeletionMode::Mark => DeletionMode::Off,
+            };
+        }
+        SETTING_POST_LAYOUT => {
+            info.settings.post_layout = match info.settings.post_layout {
+                PostLayout::Separate => PostLayout::Compact,
+                PostLayout::Compact => PostLayout::Separate,
+            };
+        }
+        _ => return ignore_stale_button(&bot, &q).await,
+    }
+
+    if !poller.update_settings(id, info.settings.clone()).await {
+        remove_buttons(&bot, &message).await?;
+        send_msg(&bot, dialogue.chat_id(), CHANNEL_NOT_FOUND_MESSAGE).await?;
+        return dialogue.update(BotState::Empty).await;
+    }
+
+    edit_interactive(
+        &bot,
+        &message,
+        &CHANNEL_SETTINGS_MESSAGE(&info),
+        &CHANNEL_SETTINGS_BUTTONS(&info.settings),
+    )
+    .await?;
+
+    dialogue
+        .update(BotState::ConfiguringChannel(
+            ConfiguringChannelBotState::ChangeSettings { message, id, info },
+        ))
+        .await
+}
+
+/// Возвращает следующее по кругу окно синхронизации правок.
+fn next_edit_window(current: chrono::Duration) -> chrono::Duration {
+    const OPTIONS: [chrono::Duration; 5] = [
+        chrono::Duration::zero(),
+        chrono::Duration::hours(1),
+        chrono::Duration::days(1),
+        chrono::Duration::days(3),
+        chrono::Duration::days(7),
+    ];
+
+    OPTIONS
+        .iter()
+        .find(|&&option| option > current)
+        .copied()
+        .unwrap_or(OPTIONS[0])
+}
+
+/// Команда `/source`.
+async fn find_post_source(bot: Bot, dialogue: BotDialogue) -> HandlerResult {
+    send_msg(&bot, dialogue.chat_id(), REQUEST_FORWARDED_MESSAGE_MESSAGE).await?;
+
+    dialogue.update(BotState::FindingPostSource).await
+}
+
+async fn receive_message_for_source(
+    bot: Bot,
+    dialogue: BotDialogue,
+    msg: Message,
+    poller: vk_poller::VkPollManager,
+    caller: Caller,
+) -> HandlerResult {
+    let (Some(chat), Some(message_id)) = (msg.forward_from_chat(), msg.forward_from_message_id())
+    else {
+        return send_msg(&bot, dialogue.chat_id(), INVALID_CHANNEL_MESSAGE).await;
+    };
+
+    let tg_channel = TelegramChannelId(chat.id.0);
+    let message_id = MessageId(message_id);
+
+    let source = poller
+        .find_post_source(tg_channel, message_id, caller.channels_owner())
+        .await;
+
+    dialogue.update(BotState::Empty).await?;
+
+    match source {
+        Some(post) => send_msg(&bot, dialogue.chat_id(), &POST_SOURCE_MESSAGE(&post)).await,
+        None => send_msg(&bot, dialogue.chat_id(), POST_SOURCE_NOT_FOUND_MESSAGE).await,
+    }
+}
+
+/// Команда `/grant`.
+async fn grant_access(
+    bot: Bot,
+    dialogue: BotDialogue,
+    access: Access,
+    caller: Caller,
+    user_id: String,
+) -> HandlerResult {
+    if caller.role != Role::GlobalAdmin {
+        return send_msg(&bot, dialogue.chat_id(), NOT_ENOUGH_RIGHTS_MESSAGE).await;
+    }
+
+    let Some(user_id) = parse_user_id(&user_id) else {
+        return send_msg(&bot, dialogue.chat_id(), GRANT_USAGE_MESSAGE).await;
+    };
+
+    if access.grant(user_id, caller.id).await {
+        log::info!(
+            "User {granted_by} granted access to user {user_id}",
+            granted_by = caller.id
+        );
+        send_msg(&bot, dialogue.chat_id(), &ACCESS_GRANTED_MESSAGE(&user_id)).await
+    } else {
+        send_msg(
+            &bot,
+            dialogue.chat_id(),
+            &ACCESS_ALREADY_GRANTED_MESSAGE(&user_id),
+        )
+        .await
+    }
+}
+
+/// Команда `/revoke`.
+async fn revoke_access(
+    bot: Bot,
+    dialogue: BotDialogue,
+    access: Access,
+    caller: Caller,
+    user_id: String,
+) -> HandlerResult {
+    if caller.role != Role::GlobalAdmin {
+        return send_msg(&bot, dialogue.chat_id(), NOT_ENOUGH_RIGHTS_MESSAGE).await;
+    }
+
+    let Some(user_id) = parse_user_id(&user_id) else {
+        return send_msg(&bot, dialogue.chat_id(), REVOKE_USAGE_MESSAGE).await;
+    };
+
+    if access.is_global_admin(user_id) {
+        return send_msg(&bot, dialogue.chat_id(), CANNOT_REVOKE_GLOBAL_ADMIN_MESSAGE).await;
+    }
+
+    if access.revoke(user_id).await {
+        log::info!("Access revoked from user {user_id}");
+        send_msg(&bot, dialogue.chat_id(), &ACCESS_REVOKED_MESSAGE(&user_id)).await
+    } else {
+        send_msg(
+            &bot,
+            dialogue.chat_id(),
+            &ACCESS_NOT_GRANTED_MESSAGE(&user_id),
+        )
+        .await
+    }
+}
+
+/// Команда `/admins`.
+async fn list_admins(
+    bot: Bot,
+    dialogue: BotDialogue,
+    access: Access,
+    caller: Caller,
+) -> HandlerResult {
+    if caller.role != Role::GlobalAdmin {
+        return send_msg(&bot, dialogue.chat_id(), NOT_ENOUGH_RIGHTS_MESSAGE).await;
+    }
+
+    let admins = access.granted().await;
+
+    if admins.is_empty() {
+        send_msg(&bot, dialogue.chat_id(), NO_ADMINS_MESSAGE).await
+    } else {
+        send_msg(&bot, dialogue.chat_id(), &LIST_ADMINS_MESSAGE(&admins)).await
+    }
+}
+
+fn parse_user_id(text: &str) -> Option<UserId> {
+    text.trim().parse().ok().map(UserId)
+}
+
 /// Команда `/cancel`.
 async fn cancel_action(bot: Bot, dialogue: BotDialogue) -> HandlerResult {
     dialogue.update(BotState::Empty).await?;
@@ -1,89 +1,604 @@
+mod access;
 mod data;
 mod dialogue;
+mod permissions;
+mod splitter;
+mod storage;
 mod utils;
 
 use crate::{
-    domain::{TelegramPost, TelegramPostPhoto},
+    domain::{
+        utf16_len, ForwardedMessage, PostLayout, TelegramChannelId, TelegramMediaKind,
+        TelegramPoll, TelegramPost, TelegramPostMedia, TelegramText,
+    },
     vk_poller,
 };
 use anyhow::Context;
+use std::sync::Arc;
 use teloxide::{
     requests::Requester,
-    types::{ChatId, InputFile, InputMedia, InputMediaPhoto, MessageId, ParseMode},
-    Bot,
+    types::{
+        ChatId, InputFile, InputMedia, InputMediaAnimation, InputMediaDocument, InputMediaPhoto,
+        InputMediaVideo, MessageEntityKind, MessageId, ParseMode,
+    },
+    ApiError, Bot, RequestError,
 };
 use tokio_util::sync::CancellationToken;
 
-pub async fn run_dialogue(bot: Bot, poller: vk_poller::VkPollManager, token: CancellationToken) {
-    let shutdown_token = dialogue::start(bot, poller).await;
+use data::{POST_DELETED_MARKDOWN_MARKER, POST_DELETED_MARKER};
+
+pub use access::Access;
+pub use storage::DbStorage;
+
+pub async fn run_dialogue(
+    bot: Bot,
+    poller: vk_poller::VkPollManager,
+    access: Access,
+    storage: Arc<DbStorage>,
+    token: CancellationToken,
+) {
+    let shutdown_token = dialogue::start(bot, poller, access, storage).await;
 
     token.cancelled().await;
     shutdown_token.shutdown().unwrap().await;
 }
 
-// TODO: Обработка частично отправленных публикаций.
-pub async fn send_post(bot: &Bot, post: TelegramPost) -> anyhow::Result<()> {
-    let chat_id = ChatId(post.channel_id.0);
+/// Шаг отправки публикации в канал.
+///
+/// Публикация отправляется несколькими запросами к Telegram. Каждый из них может
+/// завершиться ошибкой, поэтому пересылка сохраняет номер последнего успешного шага
+/// и после сбоя может продолжить с места остановки.
+pub enum PostStep {
+    Text {
+        text: TelegramText,
+        link_preview: bool,
+    },
+    Media(Vec<TelegramPostMedia>),
+    Poll(TelegramPoll),
+    Pin,
+}
+
+/// Разбивает публикацию на шаги отправки.
+///
+/// Состав шагов зависит только от публикации, поэтому после перезапуска
+/// повторная конвертация того же поста даёт те же шаги.
+pub fn post_steps(mut post: TelegramPost) -> Vec<PostStep> {
+    let mut steps = Vec::new();
+
+    // В компактном виде короткий текст становится подписью к первому вложению.
+    // Превью ссылки у подписи не показывается, поэтому такой текст остаётся отдельным.
+    if post.layout == PostLayout::Compact && !post.text.is_empty() && !post.link_preview {
+        if let Some(first) = post.media.first_mut() {
+            let caption = TelegramText::join(
+                [post.text.clone(), first.description.clone()]
+                    .into_iter()
+                    .filter(|text| !text.is_empty()),
+                "\n\n",
+            );
+
+            if utf16_len(&caption.text) <= splitter::CAPTION_LIMIT {
+                first.description = caption;
+                post.text = TelegramText::default();
+            }
+        }
+    }
 
-    let first_text_message_id = send_text(bot, chat_id, post.text).await?;
-    let first_photo_message_id = send_photos(bot, chat_id, post.photos).await?;
+    if !post.text.is_empty() {
+        let parts = splitter::split_text(post.text, splitter::MESSAGE_LIMIT);
+        let last = parts.len() - 1;
 
-    if post.is_pinned {
-        if let Some(message_id) = first_text_message_id.or(first_photo_message_id) {
-            bot.pin_chat_message(chat_id, message_id)
-                .await
-                .with_context(|| format!("pinning message {message_id} in channel {chat_id}"))?;
+        // Ссылка с превью стоит в конце текста, поэтому превью нужно только последней части.
+        for (n, text) in parts.into_iter().enumerate() {
+            steps.push(PostStep::Text {
+                text,
+                link_preview: post.link_preview && n == last,
+            });
         }
     }
 
-    Ok(())
+    // Telegram собирает в один альбом до 10 совместимых вложений.
+    let mut album: Vec<TelegramPostMedia> = Vec::new();
+
+    // Продолжения длинных подписей отправляются текстом сразу после альбома.
+    let mut overflow: Vec<TelegramText> = Vec::new();
+
+    for mut media in post.media {
+        let fits = album.len() < 10
+            && media.kind.album().is_some()
+            && album.first().map(|first| first.kind.album()) == Some(media.kind.album());
+
+        if !fits && !album.is_empty() {
+            push_album(&mut steps, std::mem::take(&mut album), &mut overflow);
+        }
+
+        let mut parts = splitter::split_text(
+            std::mem::take(&mut media.description),
+            splitter::CAPTION_LIMIT,
+        )
+        .into_iter();
+
+        media.description = parts.next().unwrap_or_default();
+        overflow.extend(parts);
+
+        album.push(media);
+    }
+
+    if !album.is_empty() {
+        push_album(&mut steps, album, &mut overflow);
+    }
+
+    if let Some(poll) = post.poll {
+        steps.push(PostStep::Poll(poll));
+    }
+
+    if post.is_pinned && !steps.is_empty() {
+        steps.push(PostStep::Pin);
+    }
+
+    steps
 }
 
-async fn send_text(bot: &Bot, chat_id: ChatId, text: String) -> anyhow::Result<Option<MessageId>> {
-    if text.is_empty() {
+fn push_album(
+    steps: &mut Vec<PostStep>,
+    album: Vec<TelegramPostMedia>,
+    overflow: &mut Vec<TelegramText>,
+) {
+    steps.push(PostStep::Media(album));
+
+    steps.extend(overflow.drain(..).map(|text| PostStep::Text {
+        text,
+        link_preview: false,
+    }));
+}
+
+/// Выполняет шаг отправки публикации.
+///
+/// `sent` — сообщения, отправленные на предыдущих шагах. Возвращает новые сообщения,
+/// включая каждую фотографию альбома, в порядке отправки.
+pub async fn send_step(
+    bot: &Bot,
+    channel_id: TelegramChannelId,
+    step: PostStep,
+    sent: &[ForwardedMessage],
+) -> anyhow::Result<Vec<ForwardedMessage>> {
+    let chat_id = ChatId(channel_id.0);
+
+    match step {
+        PostStep::Text { text, link_preview } => {
+            let message_id = send_text(bot, chat_id, text.clone(), link_preview).await?;
+
+            Ok(vec![ForwardedMessage {
+                message_id,
+                media_id: None,
+                text: Some(text.text),
+                entities: Some(text.entities),
+            }])
+        }
+        PostStep::Media(media) => send_media(bot, chat_id, media).await,
+        PostStep::Poll(poll) => {
+            let message_id = send_poll(bot, chat_id, poll.clone()).await?;
+
+            Ok(vec![ForwardedMessage {
+                message_id,
+                media_id: Some(poll.id),
+                text: None,
+                entities: None,
+            }])
+        }
+        PostStep::Pin => {
+            if let Some(message_id) = sent.first().map(|message| message.message_id) {
+                bot.pin_chat_message(chat_id, message_id)
+                    .await
+                    .with_context(|| {
+                        format!("pinning message {message_id} in channel {chat_id}")
+                    })?;
+            }
+
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Переносит правки публикации в уже отправленные сообщения.
+///
+/// Текст и подписи обновляются на месте, заменённые вложения загружаются заново.
+/// Возвращает сообщения с новым содержимым. Если после правки публикация не укладывается
+/// в прежние сообщения, например, к ней добавили фотографию, ничего не меняется
+/// и возвращается `None`.
+pub async fn edit_post(
+    bot: &Bot,
+    post: TelegramPost,
+    messages: &[ForwardedMessage],
+) -> anyhow::Result<Option<Vec<ForwardedMessage>>> {
+    enum Content {
+        Text {
+            text: TelegramText,
+            link_preview: bool,
+        },
+        Media(TelegramPostMedia),
+        Poll,
+    }
+
+    let chat_id = ChatId(post.channel_id.0);
+
+    let contents = post_steps(post)
+        .into_iter()
+        .flat_map(|step| match step {
+            PostStep::Text { text, link_preview } => vec![Content::Text { text, link_preview }],
+            PostStep::Media(media) => media.into_iter().map(Content::Media).collect(),
+            PostStep::Poll(_) => vec![Content::Poll],
+            PostStep::Pin => Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    if contents.len() != messages.len() {
         return Ok(None);
+    }
+
+    let mut edited = Vec::with_capacity(messages.len());
+
+    for (content, message) in contents.into_iter().zip(messages) {
+        let message_id = message.message_id;
+
+        let result = match content {
+            // Telegram не позволяет изменить отправленный опрос.
+            Content::Poll => {
+                edited.push(message.clone());
+                continue;
+            }
+            Content::Text { text, link_preview } => {
+                let mut request = bot.edit_message_text(chat_id, message_id, text.text.clone());
+                request.entities = Some(text.entities.clone());
+                request.disable_web_page_preview = Some(!link_preview);
+
+                edited.push(ForwardedMessage {
+                    message_id,
+                    media_id: None,
+                    text: Some(text.text),
+                    entities: Some(text.entities),
+                });
+
+                request.await.map(|_| ())
+            }
+            Content::Media(media) if message.media_id.as_deref() == Some(media.id.as_str()) => {
+                let mut request = bot.edit_message_caption(chat_id, message_id);
+                request.caption = Some(media.description.text.clone());
+                request.caption_entities = Some(media.description.entities.clone());
+
+                edited.push(ForwardedMessage {
+                    message_id,
+                    media_id: Some(media.id),
+                    text: Some(media.description.text),
+                    entities: Some(media.description.entities),
+                });
+
+                request.await.map(|_| ())
+            }
+            Content::Media(media) => {
+                edited.push(ForwardedMessage {
+                    message_id,
+                    media_id: Some(media.id.clone()),
+                    text: Some(media.description.text.clone()),
+                    entities: Some(media.description.entities.clone()),
+                });
+
+                bot.edit_message_media(chat_id, message_id, input_media(media))
+                    .await
+                    .map(|_| ())
+            }
+        };
+
+        match result {
+            // Правка могла не затронуть это сообщение.
+            Ok(()) | Err(RequestError::Api(ApiError::MessageNotModified)) => {}
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("editing message {message_id} in channel {chat_id}"));
+            }
+        }
+    }
+
+    Ok(Some(edited))
+}
+
+/// Добавляет к публикации пометку о том, что она удалена во ВКонтакте.
+///
+/// Пометка ставится в первое сообщение публикации, кроме опроса. Текст, который
+/// с пометкой не укладывается в ограничения Telegram, обрезается. Возвращает `false`,
+/// если исходный текст сообщения неизвестен, и пометку нельзя поставить, не потеряв его.
+pub async fn mark_post_deleted(
+    bot: &Bot,
+    channel_id: TelegramChannelId,
+    messages: &[ForwardedMessage],
+) -> anyhow::Result<bool> {
+    let chat_id = ChatId(channel_id.0);
+
+    let Some(message) = messages.iter().find(|message| !message.is_poll()) else {
+        return Ok(true);
     };
 
-    let mut message = bot.send_message(chat_id, text);
-    message.parse_mode = Some(ParseMode::MarkdownV2);
-    message.disable_web_page_preview = Some(true);
+    let message_id = message.message_id;
+
+    let result = match (&message.media_id, &message.text, &message.entities) {
+        (Some(_), Some(text), Some(entities)) => {
+            let mut marked = TelegramText::default();
+            marked.push_entity(POST_DELETED_MARKER, MessageEntityKind::Italic);
+
+            if !text.is_empty() {
+                marked.push("\n\n");
+                marked.append(TelegramText {
+                    text: text.clone(),
+                    entities: entities.clone(),
+                });
+            }
+
+            let marked = splitter::truncate_text(marked, splitter::CAPTION_LIMIT);
+
+            let mut request = bot.edit_message_caption(chat_id, message_id);
+            request.caption = Some(marked.text);
+            request.caption_entities = Some(marked.entities);
+
+            request.await.map(|_| ())
+        }
+        // Подписи, отправленные до перехода на сущности, не размечены.
+        (Some(_), text, _) => {
+            let caption = match text.as_deref() {
+                Some("") | None => POST_DELETED_MARKER.to_owned(),
+                Some(text) => format!("{POST_DELETED_MARKER}\n\n{text}"),
+            };
+
+            let caption =
+                splitter::truncate_text(TelegramText::plain(caption), splitter::CAPTION_LIMIT);
+
+            let mut request = bot.edit_message_caption(chat_id, message_id);
+            request.caption = Some(caption.text);
+
+            request.await.map(|_| ())
+        }
+        (None, Some(text), Some(entities)) => {
+            let mut marked = TelegramText::default();
+            marked.push_entity(POST_DELETED_MARKER, MessageEntityKind::Italic);
+            marked.push("\n\n");
+            marked.append(TelegramText {
+                text: text.clone(),
+                entities: entities.clone(),
+            });
+
+            let marked = splitter::truncate_text(marked, splitter::MESSAGE_LIMIT);
+
+            let mut request = bot.edit_message_text(chat_id, message_id, marked.text);
+            request.entities = Some(marked.entities);
+            request.disable_web_page_preview = Some(true);
+
+            request.await.map(|_| ())
+        }
+        // Сообщения, отправленные до перехода на сущности, размечены MarkdownV2.
+        // Такой текст нельзя обрезать, не сломав разметку.
+        (None, Some(text), None) => {
+            let text = format!("{POST_DELETED_MARKDOWN_MARKER}\n\n{text}");
+
+            let mut request = bot.edit_message_text(chat_id, message_id, text);
+            request.parse_mode = Some(ParseMode::MarkdownV2);
+            request.disable_web_page_preview = Some(true);
+
+            match request.await {
+                Err(RequestError::Api(
+                    ApiError::MessageIsTooLong | ApiError::EditedMessageIsTooLong,
+                )) => return Ok(false),
+                result => result.map(|_| ()),
+            }
+        }
+        (None, None, _) => return Ok(false),
+    };
+
+    match result {
+        Ok(()) | Err(RequestError::Api(ApiError::MessageNotModified)) => Ok(true),
+        Err(err) => {
+            Err(err).with_context(|| format!("marking message {message_id} in channel {chat_id}"))
+        }
+    }
+}
+
+/// Удаляет сообщения из канала, не останавливаясь на первой ошибке.
+///
+/// Сообщения, которых в канале уже нет, считаются удалёнными. Возвращает удалённые
+/// сообщения и ошибку, если удалить удалось не все.
+pub async fn delete_messages(
+    bot: &Bot,
+    channel_id: TelegramChannelId,
+    messages: &[ForwardedMessage],
+) -> (Vec<MessageId>, Option<anyhow::Error>) {
+    let chat_id = ChatId(channel_id.0);
+
+    let mut deleted = Vec::with_capacity(messages.len());
+    let mut error = None;
+
+    for message_id in messages.iter().map(|message| message.message_id) {
+        match bot.delete_message(chat_id, message_id).await {
+            Ok(_) | Err(RequestError::Api(ApiError::MessageToDeleteNotFound)) => {
+                deleted.push(message_id);
+            }
+            Err(err) => {
+                let err = anyhow::Error::new(err).context(format!(
+                    "deleting message {message_id} in channel {chat_id}"
+                ));
+
+                error.get_or_insert(err);
+            }
+        }
+    }
+
+    (deleted, error)
+}
+
+/// Закрывает опрос в канале.
+///
+/// Уже закрытый или удалённый опрос закрывать не нужно, поэтому такие ошибки игнорируются.
+pub async fn stop_poll(
+    bot: &Bot,
+    channel_id: TelegramChannelId,
+    message_id: MessageId,
+) -> anyhow::Result<()> {
+    let chat_id = ChatId(channel_id.0);
+
+    match bot.stop_poll(chat_id, message_id).await {
+        Ok(_)
+        | Err(RequestError::Api(
+            ApiError::PollHasAlreadyClosed | ApiError::MessageWithPollNotFound,
+        )) => Ok(()),
+        Err(err) => {
+            Err(err).with_context(|| format!("stopping poll {message_id} in channel {chat_id}"))
+        }
+    }
+}
+
+async fn send_text(
+    bot: &Bot,
+    chat_id: ChatId,
+    text: TelegramText,
+    link_preview: bool,
+) -> anyhow::Result<MessageId> {
+    let mut message = bot.send_message(chat_id, text.text);
+    message.entities = Some(text.entities);
+    message.disable_web_page_preview = Some(!link_preview);
 
     let message = message
         .await
         .with_context(|| format!("sending text to channel {chat_id}"))?;
 
-    Ok(Some(message.id))
+    Ok(message.id)
 }
 
-async fn send_photos(
+async fn send_poll(bot: &Bot, chat_id: ChatId, poll: TelegramPoll) -> anyhow::Result<MessageId> {
+    let mut request = bot.send_poll(chat_id, poll.question, poll.options);
+    request.is_anonymous = Some(poll.is_anonymous);
+    request.allows_multiple_answers = Some(poll.allows_multiple_answers);
+    request.is_closed = Some(poll.is_closed);
+
+    let message = request
+        .await
+        .with_context(|| format!("sending poll to channel {chat_id}"))?;
+
+    Ok(message.id)
+}
+
+async fn send_media(
     bot: &Bot,
     chat_id: ChatId,
-    photos: Vec<TelegramPostPhoto>,
-) -> anyhow::Result<Option<MessageId>> {
-    let mut first_message_id = None;
-
-    // TODO: Разбить на чанки без аллокаций.
-    let photo_collections: Vec<Vec<TelegramPostPhoto>> =
-        photos.chunks(10).map(|chunk| chunk.to_vec()).collect();
-
-    for collection in photo_collections {
-        let media = collection.into_iter().map(|photo| {
-            InputMedia::Photo(
-                InputMediaPhoto::new(InputFile::memory(photo.bytes)).caption(photo.description),
-            )
-        });
-
-        let messages = bot
-            .send_media_group(chat_id, media)
+    media: Vec<TelegramPostMedia>,
+) -> anyhow::Result<Vec<ForwardedMessage>> {
+    let sent = media
+        .iter()
+        .map(|media| (media.id.clone(), media.description.clone()))
+        .collect::<Vec<_>>();
+
+    // Альбом должен содержать хотя бы два вложения, одиночное отправляется отдельно.
+    let message_ids = if let [_] = media.as_slice() {
+        let media = media
+            .into_iter()
+            .next()
+            .expect("media should contain one item");
+        vec![send_single_media(bot, chat_id, media).await?]
+    } else {
+        bot.send_media_group(chat_id, media.into_iter().map(input_media))
             .await
-            .with_context(|| format!("sending photo to channel {chat_id}"))?;
+            .with_context(|| format!("sending media group to channel {chat_id}"))?
+            .into_iter()
+            .map(|message| message.id)
+            .collect()
+    };
 
-        if first_message_id.is_none() {
-            if let Some(message) = messages.first() {
-                first_message_id = Some(message.id);
-            }
+    Ok(message_ids
+        .into_iter()
+        .zip(sent)
+        .map(|(message_id, (media_id, caption))| ForwardedMessage {
+            message_id,
+            media_id: Some(media_id),
+            text: Some(caption.text),
+            entities: Some(caption.entities),
+        })
+        .collect())
+}
+
+async fn send_single_media(
+    bot: &Bot,
+    chat_id: ChatId,
+    media: TelegramPostMedia,
+) -> anyhow::Result<MessageId> {
+    let file = input_file(&media);
+    let caption = Some(media.description.text);
+    let caption_entities = Some(media.description.entities);
+
+    let message = match media.kind {
+        TelegramMediaKind::Photo => {
+            let mut request = bot.send_photo(chat_id, file);
+            request.caption = caption;
+            request.caption_entities = caption_entities;
+            request.await
         }
+        TelegramMediaKind::Video => {
+            let mut request = bot.send_video(chat_id, file);
+            request.caption = caption;
+            request.caption_entities = caption_entities;
+            request.supports_streaming = Some(true);
+            request.await
+        }
+        TelegramMediaKind::Document => {
+            let mut request = bot.send_document(chat_id, file);
+            request.caption = caption;
+            request.caption_entities = caption_entities;
+            request.await
+        }
+        TelegramMediaKind::Animation => {
+            let mut request = bot.send_animation(chat_id, file);
+            request.caption = caption;
+            request.caption_entities = caption_entities;
+            request.await
+        }
+    };
+
+    let message = message.with_context(|| format!("sending media to channel {chat_id}"))?;
+
+    Ok(message.id)
+}
+
+fn input_media(media: TelegramPostMedia) -> InputMedia {
+    let file = input_file(&media);
+    let TelegramText {
+        text: caption,
+        entities: caption_entities,
+    } = media.description;
+
+    match media.kind {
+        TelegramMediaKind::Photo => InputMedia::Photo(
+            InputMediaPhoto::new(file)
+                .caption(caption)
+                .caption_entities(caption_entities),
+        ),
+        TelegramMediaKind::Video => InputMedia::Video(
+            InputMediaVideo::new(file)
+                .caption(caption)
+                .caption_entities(caption_entities)
+                .supports_streaming(true),
+        ),
+        TelegramMediaKind::Document => InputMedia::Document(
+            InputMediaDocument::new(file)
+                .caption(caption)
+                .caption_entities(caption_entities),
+        ),
+        TelegramMediaKind::Animation => InputMedia::Animation(
+            InputMediaAnimation::new(file)
+                .caption(caption)
+                .caption_entities(caption_entities),
+        ),
     }
+}
 
-    Ok(first_message_id)
+fn input_file(media: &TelegramPostMedia) -> InputFile {
+    let file = InputFile::memory(media.bytes.clone());
+
+    match &media.file_name {
+        Some(file_name) => file.file_name(file_name.clone()),
+        None => file,
+    }
 }
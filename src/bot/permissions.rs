@@ -0,0 +1,71 @@
+use teloxide::{
+    prelude::*,
+    types::{ChatMemberKind, UserId},
+    RequestError,
+};
+
+/// Право в канале, которого не хватает для пересылки постов.
+#[derive(Clone, Copy, Debug)]
+pub enum MissingPermission {
+    /// Бот не добавлен в администраторы канала.
+    BotIsNotAdmin,
+
+    /// Бот не может публиковать сообщения.
+    BotCannotPost,
+
+    /// Бот не может редактировать и закреплять сообщения.
+    /// В каналах закрепление входит в право редактирования.
+    BotCannotEdit,
+
+    /// Пользователь, добавляющий канал, не является его администратором.
+    UserIsNotAdmin,
+}
+
+/// Проверяет, что бот может публиковать, редактировать и закреплять посты в канале,
+/// а пользователь, который его добавляет, является администратором канала.
+pub async fn check_channel_permissions(
+    bot: &Bot,
+    channel_id: ChatId,
+    user_id: UserId,
+) -> anyhow::Result<Option<MissingPermission>> {
+    let me = bot.get_me().await?;
+
+    let bot_member = match bot.get_chat_member(channel_id, me.id).await {
+        Ok(member) => member,
+        // Если бот не состоит в канале, Telegram не даёт получить информацию о нём.
+        Err(RequestError::Api(err)) => {
+            log::debug!("Failed to get bot membership in channel {channel_id}: {err}");
+            return Ok(Some(MissingPermission::BotIsNotAdmin));
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    match &bot_member.kind {
+        ChatMemberKind::Owner(_) => {}
+        ChatMemberKind::Administrator(admin) => {
+            if !admin.can_post_messages {
+                return Ok(Some(MissingPermission::BotCannotPost));
+            }
+
+            if !admin.can_edit_messages {
+                return Ok(Some(MissingPermission::BotCannotEdit));
+            }
+        }
+        _ => return Ok(Some(MissingPermission::BotIsNotAdmin)),
+    }
+
+    let user_member = match bot.get_chat_member(channel_id, user_id).await {
+        Ok(member) => member,
+        Err(RequestError::Api(err)) => {
+            log::debug!("Failed to get user {user_id} membership in channel {channel_id}: {err}");
+            return Ok(Some(MissingPermission::UserIsNotAdmin));
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    if !user_member.is_privileged() {
+        return Ok(Some(MissingPermission::UserIsNotAdmin));
+    }
+
+    Ok(None)
+}
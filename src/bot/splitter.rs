@@ -0,0 +1,427 @@
+//! Разбиение длинных текстов под ограничения Telegram.
+//!
+//! Публикация ВКонтакте может быть длиной до 16 тысяч символов, а Telegram принимает
+//! не больше 4096 символов в сообщении и 1024 в подписи к вложению. Текст режется
+//! по абзацам, затем по предложениям, затем по словам, и никогда внутри ссылки
+//! или другой сущности, если она сама укладывается в ограничение.
+
+use crate::domain::{utf16_len, TelegramText};
+use teloxide::types::MessageEntity;
+
+/// Максимальная длина текста сообщения.
+pub const MESSAGE_LIMIT: usize = 4096;
+
+/// Максимальная длина подписи к вложению.
+pub const CAPTION_LIMIT: usize = 1024;
+
+/// Запас под нумерацию частей вида `(12/34) `.
+const NUMBERING_RESERVE: usize = 16;
+
+/// Места разреза в порядке предпочтения. Разрез делается после разделителя.
+const SEPARATORS: [&[&str]; 4] = [&["\n\n"], &["\n"], &[". ", "! ", "? ", "… "], &[" "]];
+
+/// Разбивает текст на части не длиннее `limit` и нумерует их, если частей больше одной.
+pub fn split_text(text: TelegramText, limit: usize) -> Vec<TelegramText> {
+    if utf16_len(&text.text) <= limit {
+        return vec![text];
+    }
+
+    let ranges = entity_ranges(&text);
+    let parts = split_ranges(&text.text, &ranges, limit - NUMBERING_RESERVE);
+    let total = parts.len();
+
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(n, (start, end))| {
+            let mut part = TelegramText::plain(format!("({}/{total}) ", n + 1));
+            part.append(slice(&text, &ranges, start, end));
+            part
+        })
+        .collect()
+}
+
+/// Обрезает текст до `limit` символов, ставя в конце многоточие.
+pub fn truncate_text(text: TelegramText, limit: usize) -> TelegramText {
+    if utf16_len(&text.text) <= limit {
+        return text;
+    }
+
+    let ranges = entity_ranges(&text);
+    let end = max_end(&text.text, 0, limit - '…'.len_utf16());
+
+    let mut truncated = slice(&text, &ranges, 0, end);
+    truncated.push("…");
+    truncated
+}
+
+/// Находит границы частей в байтах.
+fn split_ranges(text: &str, ranges: &[(usize, usize)], limit: usize) -> Vec<(usize, usize)> {
+    let mut parts = Vec::new();
+    let mut start = skip_whitespace(text, 0);
+
+    while start < text.len() {
+        let max_end = max_end(text, start, limit);
+
+        let end = if max_end == text.len() {
+            max_end
+        } else {
+            find_cut(text, ranges, start, max_end, limit)
+        };
+
+        let trimmed_end = start + text[start..end].trim_end().len();
+
+        if trimmed_end > start {
+            parts.push((start, trimmed_end));
+        }
+
+        start = skip_whitespace(text, end);
+    }
+
+    parts
+}
+
+/// Выбирает место разреза между `start` и `max_end`.
+///
+/// Разрез ищется во второй половине окна, чтобы не получались слишком короткие части.
+fn find_cut(
+    text: &str,
+    ranges: &[(usize, usize)],
+    start: usize,
+    max_end: usize,
+    limit: usize,
+) -> usize {
+    let window = &text[start..max_end];
+    let min_cut = start + window.len() / 2;
+
+    for separators in SEPARATORS {
+        let cut = separators
+            .iter()
+            .filter_map(|separator| {
+                window
+                    .rmatch_indices(separator)
+                    .map(|(idx, separator)| start + idx + separator.len())
+                    .find(|cut| *cut > min_cut && !is_inside_entity(ranges, *cut))
+            })
+            .max();
+
+        if let Some(cut) = cut {
+            return cut;
+        }
+    }
+
+    // Подходящего разделителя нет. Сущность, которая не помещается целиком,
+    // переносится в следующую часть, если только она не длиннее самого ограничения.
+    ranges
+        .iter()
+        .filter(|(entity_start, entity_end)| *entity_start < max_end && max_end < *entity_end)
+        .filter(|(entity_start, entity_end)| utf16_len(&text[*entity_start..*entity_end]) <= limit)
+        .map(|(entity_start, _)| *entity_start)
+        .filter(|entity_start| *entity_start > start)
+        .min()
+        .unwrap_or(max_end)
+}
+
+/// Наибольшая граница символа, до которой текст от `start` укладывается в `limit`.
+fn max_end(text: &str, start: usize, limit: usize) -> usize {
+    let mut length = 0;
+
+    for (idx, chr) in text[start..].char_indices() {
+        length += chr.len_utf16();
+
+        if length > limit {
+            return start + idx;
+        }
+    }
+
+    text.len()
+}
+
+fn skip_whitespace(text: &str, start: usize) -> usize {
+    text.len() - text[start..].trim_start().len()
+}
+
+fn is_inside_entity(ranges: &[(usize, usize)], pos: usize) -> bool {
+    ranges.iter().any(|(start, end)| *start < pos && pos < *end)
+}
+
+/// Вырезает часть текста вместе с попадающими в неё сущностями.
+fn slice(text: &TelegramText, ranges: &[(usize, usize)], start: usize, end: usize) -> TelegramText {
+    let entities = text
+        .entities
+        .iter()
+        .zip(ranges)
+        .filter(|(_, (entity_start, entity_end))| *entity_start < end && *entity_end > start)
+        .map(|(entity, (entity_start, entity_end))| {
+            let clipped_start = (*entity_start).max(start);
+            let clipped_end = (*entity_end).min(end);
+
+            MessageEntity {
+                kind: entity.kind.clone(),
+                offset: utf16_len(&text.text[start..clipped_start]),
+                length: utf16_len(&text.text[clipped_start..clipped_end]),
+            }
+        })
+        .filter(|entity| entity.length > 0)
+        .collect();
+
+    TelegramText {
+        text: text.text[start..end].to_owned(),
+        entities,
+    }
+}
+
+/// Переводит смещения сущностей из UTF-16 в байты.
+fn entity_ranges(text: &TelegramText) -> Vec<(usize, usize)> {
+    text.entities
+        .iter()
+        .map(|entity| {
+            let start = utf16_to_byte(&text.text, entity.offset);
+            let end = utf16_to_byte(&text.text, entity.offset + entity.length);
+            (start, end)
+        })
+        .collect()
+}
+
+fn utf16_to_byte(text: &str, offset: usize) -> usize {
+    let mut length = 0;
+
+    for (idx, chr) in text.char_indices() {
+        if length >= offset {
+            return idx;
+        }
+
+        length += chr.len_utf16();
+    }
+
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use teloxide::types::MessageEntityKind;
+    use url::Url;
+
+    /// Ограничение, при котором на текст части остаётся ровно 100 символов.
+    const TEST_LIMIT: usize = 100 + NUMBERING_RESERVE;
+
+    /// Убирает нумерацию из частей, проверяя её.
+    fn bodies(parts: &[TelegramText]) -> Vec<TelegramText> {
+        let total = parts.len();
+
+        parts
+            .iter()
+            .enumerate()
+            .map(|(n, part)| {
+                let prefix = format!("({}/{total}) ", n + 1);
+                let prefix_len = utf16_len(&prefix);
+
+                let text = part
+                    .text
+                    .strip_prefix(&prefix)
+                    .unwrap_or_else(|| panic!("part {part:?} should start with {prefix:?}"));
+
+                let entities = part
+                    .entities
+                    .iter()
+                    .map(|entity| MessageEntity {
+                        kind: entity.kind.clone(),
+                        offset: entity.offset - prefix_len,
+                        length: entity.length,
+                    })
+                    .collect();
+
+                TelegramText {
+                    text: text.to_owned(),
+                    entities,
+                }
+            })
+            .collect()
+    }
+
+    fn texts(parts: &[TelegramText]) -> Vec<String> {
+        bodies(parts).into_iter().map(|part| part.text).collect()
+    }
+
+    fn link() -> MessageEntityKind {
+        MessageEntityKind::TextLink {
+            url: Url::parse("https://example.com").unwrap(),
+        }
+    }
+
+    fn assert_fits(parts: &[TelegramText], limit: usize) {
+        for part in parts {
+            let length = utf16_len(&part.text);
+            assert!(length <= limit, "part of length {length} exceeds {limit}");
+
+            for entity in &part.entities {
+                assert!(entity.length > 0);
+                assert!(entity.offset + entity.length <= length);
+            }
+        }
+    }
+
+    #[test]
+    fn short_text_is_not_split() {
+        let text = TelegramText::plain("Короткий текст");
+        assert_eq!(split_text(text.clone(), MESSAGE_LIMIT), [text]);
+    }
+
+    #[test]
+    fn cuts_at_paragraph_first() {
+        let paragraph = "Sentence one. ".repeat(5);
+        let paragraph = paragraph.trim_end();
+        let text = format!("{paragraph}\n\n{}", "word ".repeat(30));
+
+        let parts = split_text(TelegramText::plain(text), TEST_LIMIT);
+
+        assert_eq!(texts(&parts)[0], paragraph);
+        assert_fits(&parts, TEST_LIMIT);
+    }
+
+    #[test]
+    fn cuts_at_sentence_without_paragraphs() {
+        let sentences = "Sentence one. ".repeat(5);
+        let text = format!("{sentences}{}", "word ".repeat(30));
+
+        let parts = split_text(TelegramText::plain(text), TEST_LIMIT);
+
+        assert_eq!(texts(&parts)[0], sentences.trim_end());
+        assert_fits(&parts, TEST_LIMIT);
+    }
+
+    #[test]
+    fn cuts_at_word_without_sentences() {
+        let text = "word ".repeat(40);
+        let half = ["word"; 20].join(" ");
+
+        let parts = split_text(TelegramText::plain(text), TEST_LIMIT);
+
+        assert_eq!(texts(&parts), [half.clone(), half]);
+        assert_fits(&parts, TEST_LIMIT);
+    }
+
+    #[test]
+    fn moves_straddling_link_to_next_part() {
+        let mut text = TelegramText::plain("a ".repeat(45));
+        text.push_entity("example link text", link());
+        text.push(" tail and some more words");
+
+        let parts = bodies(&split_text(text, TEST_LIMIT));
+
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].entities.is_empty());
+        assert_eq!(parts[1].text, "example link text tail and some more words");
+        assert_eq!(
+            parts[1].entities,
+            [MessageEntity {
+                kind: link(),
+                offset: 0,
+                length: 17,
+            }]
+        );
+    }
+
+    #[test]
+    fn moves_straddling_link_without_separators() {
+        let mut text = TelegramText::plain("a".repeat(90));
+        text.push_entity(&"b".repeat(20), link());
+        text.push(&"c".repeat(20));
+
+        let parts = bodies(&split_text(text, TEST_LIMIT));
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].text, "a".repeat(90));
+        assert!(parts[0].entities.is_empty());
+        assert_eq!(
+            parts[1].text,
+            format!("{}{}", "b".repeat(20), "c".repeat(20))
+        );
+        assert_eq!(parts[1].entities.len(), 1);
+        assert_eq!(parts[1].entities[0].offset, 0);
+        assert_eq!(parts[1].entities[0].length, 20);
+    }
+
+    #[test]
+    fn clips_entity_longer_than_limit() {
+        let mut text = TelegramText::plain("Начало ");
+        text.push_entity(&"ж".repeat(250), MessageEntityKind::Bold);
+
+        let parts = split_text(text, TEST_LIMIT);
+        assert_fits(&parts, TEST_LIMIT);
+
+        let bodies = bodies(&parts);
+
+        assert_eq!(
+            texts(&parts),
+            [
+                format!("Начало {}", "ж".repeat(93)),
+                "ж".repeat(100),
+                "ж".repeat(57),
+            ]
+        );
+
+        let ranges = bodies
+            .iter()
+            .map(|part| {
+                let [entity] = part.entities.as_slice() else {
+                    panic!("part {part:?} should contain one entity");
+                };
+                (entity.offset, entity.length)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(ranges, [(7, 93), (0, 100), (0, 57)]);
+
+        // Смещения в частях с нумерацией учитывают префикс.
+        assert_eq!(parts[1].entities[0].offset, "(2/3) ".len());
+    }
+
+    #[test]
+    fn keeps_surrogate_pairs_whole() {
+        // Эмодзи занимает две единицы UTF-16, поэтому граница в 100 единиц
+        // приходится на середину эмодзи.
+        let mut text = TelegramText::plain("a");
+        text.push_entity(&"😀".repeat(60), MessageEntityKind::Bold);
+
+        let parts = split_text(text, TEST_LIMIT);
+        assert_fits(&parts, TEST_LIMIT);
+
+        let bodies = bodies(&parts);
+
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[0].text, format!("a{}", "😀".repeat(49)));
+        assert_eq!(bodies[1].text, "😀".repeat(11));
+
+        assert_eq!(bodies[0].entities[0].offset, 1);
+        assert_eq!(bodies[0].entities[0].length, 98);
+        assert_eq!(bodies[1].entities[0].offset, 0);
+        assert_eq!(bodies[1].entities[0].length, 22);
+    }
+
+    #[test]
+    fn numbered_parts_fit_into_limits() {
+        let mut text = TelegramText::default();
+
+        for n in 0..1000 {
+            match n % 4 {
+                0 => text.push("Обычное предложение с эмодзи 😀. "),
+                1 => text.push_entity("ссылка в тексте", link()),
+                2 => text.push(" и ещё немного слов\n"),
+                _ => text.push_entity("#тег\n\n", MessageEntityKind::Hashtag),
+            }
+        }
+
+        for limit in [MESSAGE_LIMIT, CAPTION_LIMIT] {
+            let parts = split_text(text.clone(), limit);
+
+            assert!(parts.len() > 1);
+            assert_fits(&parts, limit);
+
+            let joined = texts(&parts).concat();
+            let expected = text.text.split_whitespace().collect::<String>();
+            assert_eq!(joined.split_whitespace().collect::<String>(), expected);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::db::Db;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{future::Future, pin::Pin, sync::Arc};
+use teloxide::{dispatching::dialogue::Storage, types::ChatId};
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Хранилище состояний диалогов в базе данных.
+///
+/// Состояние сохраняется в JSON, поэтому незавершённые команды переживают перезапуск.
+/// Диалоги, которые не менялись дольше `timeout`, считаются брошенными и сбрасываются.
+pub struct DbStorage {
+    db: Db,
+    timeout: chrono::Duration,
+}
+
+impl DbStorage {
+    pub fn new(db: Db, timeout: chrono::Duration) -> Arc<Self> {
+        Arc::new(Self { db, timeout })
+    }
+}
+
+impl<D> Storage<D> for DbStorage
+where
+    D: Serialize + DeserializeOwned + Send + 'static,
+{
+    type Error = anyhow::Error;
+
+    fn remove_dialogue(self: Arc<Self>, chat_id: ChatId) -> BoxFuture<anyhow::Result<()>> {
+        Box::pin(async move {
+            self.db.remove_dialogue_state(chat_id.0).await;
+            Ok(())
+        })
+    }
+
+    fn update_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+        dialogue: D,
+    ) -> BoxFuture<anyhow::Result<()>> {
+        Box::pin(async move {
+            let state = serde_json::to_string(&dialogue)?;
+            self.db.set_dialogue_state(chat_id.0, state).await;
+            Ok(())
+        })
+    }
+
+    fn get_dialogue(self: Arc<Self>, chat_id: ChatId) -> BoxFuture<anyhow::Result<Option<D>>> {
+        Box::pin(async move {
+            let Some((state, updated)) = self.db.get_dialogue_state(chat_id.0).await else {
+                return Ok(None);
+            };
+
+            if chrono::Utc::now() - updated > self.timeout {
+                log::debug!("Dialogue in chat {chat_id} expired, resetting state");
+
+                self.db.remove_dialogue_state(chat_id.0).await;
+                return Ok(None);
+            }
+
+            // Состояние, сохранённое предыдущей версией бота, может не совпадать
+            // с текущим форматом. В этом случае диалог просто начинается заново.
+            match serde_json::from_str(&state) {
+                Ok(dialogue) => Ok(Some(dialogue)),
+                Err(err) => {
+                    log::warn!(
+                        "Failed to restore dialogue in chat {chat_id}, resetting state: {err}"
+                    );
+
+                    self.db.remove_dialogue_state(chat_id.0).await;
+                    Ok(None)
+                }
+            }
+        })
+    }
+}
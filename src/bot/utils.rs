@@ -1,6 +1,7 @@
 use super::dialogue::BotDialogue;
 use teloxide::prelude::*;
-use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, InputFile};
+use url::Url;
 
 #[inline(always)]
 pub async fn send_msg(bot: &Bot, chat_id: ChatId, text: &str) -> anyhow::Result<()> {
@@ -20,6 +21,21 @@ pub async fn edit_msg(bot: &Bot, msg: &Message, text: &str) -> anyhow::Result<()
     Ok(())
 }
 
+#[inline(always)]
+pub async fn edit_interactive(
+    bot: &Bot,
+    msg: &Message,
+    text: &str,
+    buttons: &[(&str, (usize, &str))],
+) -> anyhow::Result<()> {
+    bot.edit_message_text(msg.chat.id, msg.id, text)
+        .reply_markup(buttons_to_inline_keyboard(buttons))
+        .disable_web_page_preview(true)
+        .await?;
+
+    Ok(())
+}
+
 #[inline(always)]
 pub async fn send_interative(
     bot: &Bot,
@@ -36,6 +52,48 @@ pub async fn send_interative(
     Ok(message)
 }
 
+#[inline(always)]
+pub async fn send_interactive_photo(
+    bot: &Bot,
+    dialogue: &BotDialogue,
+    photo: Url,
+    caption: &str,
+    buttons: &[(&str, (usize, &str))],
+) -> anyhow::Result<Message> {
+    let message = bot
+        .send_photo(dialogue.chat_id(), InputFile::url(photo))
+        .caption(caption)
+        .reply_markup(buttons_to_inline_keyboard(buttons))
+        .await?;
+
+    Ok(message)
+}
+
+#[inline(always)]
+pub async fn remove_buttons(bot: &Bot, msg: &Message) -> anyhow::Result<()> {
+    bot.edit_message_reply_markup(msg.chat.id, msg.id).await?;
+
+    Ok(())
+}
+
+/// Отвечает на нажатие кнопки, которая не относится к текущему шагу диалога,
+/// и убирает клавиатуру с её сообщения.
+///
+/// Кнопки остаются нажимаемыми после отмены команды или смены состояния,
+/// поэтому такие нажатия ожидаемы и просто игнорируются.
+pub async fn ignore_stale_button(bot: &Bot, q: &CallbackQuery) -> anyhow::Result<()> {
+    bot.answer_callback_query(q.id.clone()).await?;
+
+    if let Some(message) = &q.message {
+        // Клавиатура могла быть уже убрана.
+        if let Err(err) = remove_buttons(bot, message).await {
+            log::debug!("Failed to remove stale buttons: {err:#}");
+        }
+    }
+
+    Ok(())
+}
+
 #[inline(always)]
 fn buttons_to_inline_keyboard(buttons: &[(&str, (usize, &str))]) -> InlineKeyboardMarkup {
     InlineKeyboardMarkup::new((0..buttons.len()).map(|idx| {
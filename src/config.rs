@@ -6,6 +6,7 @@ use std::{
     fs,
     path::{Path, PathBuf},
 };
+use teloxide::types::UserId;
 
 #[derive(Clone, Debug, Deserialize, Validate)]
 pub struct Config {
@@ -27,10 +28,64 @@ pub struct Vk {
     #[garde(length(min = 1))]
     pub service_key: String,
 
+    #[serde(default)]
+    #[garde(dive)]
+    pub rate_limit: VkRateLimit,
+
+    #[serde(default)]
+    #[garde(dive)]
+    pub retries: VkRetries,
+
     #[garde(dive)]
     pub debug: Option<VkDebug>,
 }
 
+#[derive(Clone, Debug, Deserialize, Validate)]
+pub struct VkRateLimit {
+    /// Количество запросов в секунду, общее для всех каналов.
+    #[garde(range(min = 1))]
+    pub requests_per_second: u32,
+
+    /// Количество запросов, которые можно выполнить разом после простоя.
+    #[garde(range(min = 1))]
+    pub burst: u32,
+}
+
+impl Default for VkRateLimit {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 3,
+            burst: 3,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Validate)]
+pub struct VkRetries {
+    /// Максимальное количество попыток вызова метода, включая первую.
+    #[garde(range(min = 1))]
+    pub max_attempts: u32,
+
+    /// Задержка перед второй попыткой в миллисекундах.
+    /// Каждая следующая задержка увеличивается вдвое.
+    #[garde(range(min = 1))]
+    pub initial_delay_ms: u64,
+
+    /// Максимальная задержка между попытками в миллисекундах.
+    #[garde(range(min = self.initial_delay_ms))]
+    pub max_delay_ms: u64,
+}
+
+impl Default for VkRetries {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Validate)]
 pub struct VkDebug {
     #[garde(skip)]
@@ -44,6 +99,20 @@ pub struct VkDebug {
 pub struct Telegram {
     #[garde(length(min = 1))]
     pub bot_token: String,
+
+    /// Идентификаторы пользователей Telegram с полным доступом к боту.
+    /// Только они могут выдавать и отзывать доступ у других пользователей.
+    #[garde(length(min = 1))]
+    pub admins: Vec<UserId>,
+
+    /// Время в секундах, после которого незавершённая команда сбрасывается.
+    #[serde(default = "default_dialogue_timeout_secs")]
+    #[garde(range(min = 1))]
+    pub dialogue_timeout_secs: u32,
+}
+
+fn default_dialogue_timeout_secs() -> u32 {
+    60 * 60
 }
 
 #[derive(Clone, Debug, Deserialize, Validate)]
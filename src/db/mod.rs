@@ -1,10 +1,14 @@
 mod models;
 mod schema;
 
-use crate::domain::{ChannelEntryId, ChannelInfo};
+use crate::domain::{
+    ChannelEntryId, ChannelInfo, ChannelSettings, ForwardedMessage, PostLayout, TelegramChannelId,
+    TrackedPost, UnfinishedPost, VkOwnerId, VkPostId,
+};
 use anyhow::{anyhow, bail, Context};
 use diesel::{
-    Connection, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper, SqliteConnection,
+    Connection, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl, SelectableHelper,
+    SqliteConnection,
 };
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use std::{
@@ -13,6 +17,7 @@ use std::{
     path::Path,
     sync::{Arc, Mutex},
 };
+use teloxide::types::{MessageEntity, MessageId, UserId};
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
 
@@ -89,7 +94,8 @@ impl Db {
         Ok(())
     }
 
-    pub async fn get_channels(&self) -> Vec<(ChannelEntryId, ChannelInfo)> {
+    /// Возвращает каналы пользователя `owner` или все каналы, если он не указан.
+    pub async fn get_channels(&self, owner: Option<UserId>) -> Vec<(ChannelEntryId, ChannelInfo)> {
         let conn = self.conn.clone();
 
         tokio::task::spawn_blocking(move || {
@@ -97,8 +103,15 @@ impl Db {
 
             let mut conn = conn.lock().expect("connection shouldn't be poisoned");
 
-            channels::table
+            let mut query = channels::table
                 .select(models::Channel::as_select())
+                .into_boxed();
+
+            if let Some(owner) = owner {
+                query = query.filter(channels::owner_user_id.eq(owner.0 as i64));
+            }
+
+            query
                 .load(&mut *conn)
                 .expect("database operations should be successful")
                 .into_iter()
@@ -130,9 +143,10 @@ impl Db {
         .expect("database queries shouldn't panic")
     }
 
+    /// Сохраняет состояние опроса канала, не трогая его настройки.
     pub async fn update_channel(&self, id: ChannelEntryId, info: &ChannelInfo) {
         let row_id: i32 = id.0;
-        let info: models::NewChannel = info.to_owned().into();
+        let info = models::ChannelPollState::from(info);
 
         let conn = self.conn.clone();
 
@@ -151,8 +165,9 @@ impl Db {
         .expect("database queries shouldn't panic")
     }
 
-    pub async fn remove_channel(&self, id: ChannelEntryId) {
+    pub async fn update_channel_settings(&self, id: ChannelEntryId, settings: &ChannelSettings) {
         let row_id: i32 = id.0;
+        let settings = models::ChannelSettings::from(settings);
 
         let conn = self.conn.clone();
 
@@ -161,12 +176,689 @@ impl Db {
 
             let mut conn = conn.lock().expect("connection shouldn't be poisoned");
 
-            diesel::delete(channels::table)
+            diesel::update(channels::table)
                 .filter(channels::id.eq(row_id))
+                .set(settings)
+                .execute(&mut *conn)
+                .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    pub async fn remove_channel(&self, id: ChannelEntryId) {
+        let row_id: i32 = id.0;
+
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::{channels, forwarded_messages, forwarded_posts};
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            conn.transaction(|conn| {
+                diesel::delete(forwarded_messages::table)
+                    .filter(forwarded_messages::channel_id.eq(row_id))
+                    .execute(conn)?;
+
+                diesel::delete(forwarded_posts::table)
+                    .filter(forwarded_posts::channel_id.eq(row_id))
+                    .execute(conn)?;
+
+                diesel::delete(channels::table)
+                    .filter(channels::id.eq(row_id))
+                    .execute(conn)
+            })
+            .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Проверяет, была ли публикация уже переслана в канал или пересылается сейчас.
+    pub async fn is_post_forwarded(
+        &self,
+        channel: ChannelEntryId,
+        owner_id: VkOwnerId,
+        post_id: i64,
+    ) -> bool {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_posts;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            forwarded_posts::table
+                .filter(forwarded_posts::channel_id.eq(channel.0))
+                .filter(forwarded_posts::vk_owner_id.eq(owner_id.0))
+                .filter(forwarded_posts::vk_post_id.eq(post_id))
+                .count()
+                .get_result::<i64>(&mut *conn)
+                .expect("database operations should be successful")
+                > 0
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Запоминает, что началась пересылка публикации в канал.
+    pub async fn start_forwarded_post(
+        &self,
+        channel: ChannelEntryId,
+        post: VkPostId,
+        post_datetime: chrono::DateTime<chrono::Utc>,
+        content_hash: String,
+        layout: PostLayout,
+    ) {
+        let forwarded_post = models::ForwardedPost {
+            channel_id: channel.0,
+            vk_owner_id: post.owner_id.0,
+            vk_post_id: post.post_id,
+            vk_post_timestamp: post_datetime.timestamp(),
+            forwarded_timestamp: chrono::Utc::now().timestamp(),
+            sent_steps: 0,
+            is_sent: false,
+            content_hash: Some(content_hash),
+            is_deleted: false,
+            post_layout: layout.as_str().to_owned(),
+        };
+
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_posts;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            diesel::insert_or_ignore_into(forwarded_posts::table)
+                .values(forwarded_post)
+                .execute(&mut *conn)
+                .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Запоминает выполненный шаг отправки публикации и полученные сообщения.
+    pub async fn add_forwarded_step(
+        &self,
+        channel: ChannelEntryId,
+        tg_channel: TelegramChannelId,
+        post: VkPostId,
+        sent_steps: usize,
+        messages: &[ForwardedMessage],
+    ) {
+        let messages = messages.to_vec();
+        let sent_steps = i32::try_from(sent_steps).expect("post should have few steps");
+
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::{forwarded_messages, forwarded_posts};
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            conn.transaction(|conn| {
+                let sent_messages = forwarded_messages::table
+                    .filter(forwarded_messages::channel_id.eq(channel.0))
+                    .filter(forwarded_messages::vk_owner_id.eq(post.owner_id.0))
+                    .filter(forwarded_messages::vk_post_id.eq(post.post_id))
+                    .count()
+                    .get_result::<i64>(conn)?;
+
+                let messages = messages
+                    .into_iter()
+                    .zip(sent_messages..)
+                    .map(|(message, position)| models::ForwardedMessage {
+                        tg_chat_id: tg_channel.0,
+                        tg_message_id: message.message_id.0,
+                        channel_id: channel.0,
+                        vk_owner_id: post.owner_id.0,
+                        vk_post_id: post.post_id,
+                        position: position as i32,
+                        media_id: message.media_id,
+                        text: message.text,
+                        poll_close_timestamp: None,
+                        entities: message.entities.as_deref().map(serialize_entities),
+                    })
+                    .collect::<Vec<_>>();
+
+                diesel::insert_or_ignore_into(forwarded_messages::table)
+                    .values(messages)
+                    .execute(conn)?;
+
+                diesel::update(forwarded_posts::table)
+                    .filter(forwarded_posts::channel_id.eq(channel.0))
+                    .filter(forwarded_posts::vk_owner_id.eq(post.owner_id.0))
+                    .filter(forwarded_posts::vk_post_id.eq(post.post_id))
+                    .set(forwarded_posts::sent_steps.eq(sent_steps))
+                    .execute(conn)
+            })
+            .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Отмечает публикацию как отправленную целиком.
+    pub async fn finish_forwarded_post(&self, channel: ChannelEntryId, post: VkPostId) {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_posts;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            diesel::update(forwarded_posts::table)
+                .filter(forwarded_posts::channel_id.eq(channel.0))
+                .filter(forwarded_posts::vk_owner_id.eq(post.owner_id.0))
+                .filter(forwarded_posts::vk_post_id.eq(post.post_id))
+                .set(forwarded_posts::is_sent.eq(true))
+                .execute(&mut *conn)
+                .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Забывает отправленные сообщения публикации, чтобы отправить её заново.
+    pub async fn reset_forwarded_post(&self, channel: ChannelEntryId, post: VkPostId) {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::{forwarded_messages, forwarded_posts};
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            conn.transaction(|conn| {
+                diesel::delete(forwarded_messages::table)
+                    .filter(forwarded_messages::channel_id.eq(channel.0))
+                    .filter(forwarded_messages::vk_owner_id.eq(post.owner_id.0))
+                    .filter(forwarded_messages::vk_post_id.eq(post.post_id))
+                    .execute(conn)?;
+
+                diesel::update(forwarded_posts::table)
+                    .filter(forwarded_posts::channel_id.eq(channel.0))
+                    .filter(forwarded_posts::vk_owner_id.eq(post.owner_id.0))
+                    .filter(forwarded_posts::vk_post_id.eq(post.post_id))
+                    .set(forwarded_posts::sent_steps.eq(0))
+                    .execute(conn)
+            })
+            .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Забывает сообщения публикации, которые были удалены из канала.
+    pub async fn forget_forwarded_messages(
+        &self,
+        channel: ChannelEntryId,
+        post: VkPostId,
+        messages: &[MessageId],
+    ) {
+        let conn = self.conn.clone();
+        let message_ids: Vec<i32> = messages.iter().map(|message_id| message_id.0).collect();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_messages;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            diesel::delete(forwarded_messages::table)
+                .filter(forwarded_messages::channel_id.eq(channel.0))
+                .filter(forwarded_messages::vk_owner_id.eq(post.owner_id.0))
+                .filter(forwarded_messages::vk_post_id.eq(post.post_id))
+                .filter(forwarded_messages::tg_message_id.eq_any(message_ids))
+                .execute(&mut *conn)
+                .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Возвращает самую раннюю публикацию канала, отправка которой была прервана.
+    pub async fn get_unfinished_post(&self, channel: ChannelEntryId) -> Option<UnfinishedPost> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_posts;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            forwarded_posts::table
+                .filter(forwarded_posts::channel_id.eq(channel.0))
+                .filter(forwarded_posts::is_sent.eq(false))
+                .order(forwarded_posts::vk_post_id.asc())
+                .select(models::ForwardedPost::as_select())
+                .first(&mut *conn)
+                .optional()
+                .expect("database operations should be successful")
+                .map(|post| UnfinishedPost {
+                    post: VkPostId {
+                        owner_id: VkOwnerId(post.vk_owner_id),
+                        post_id: post.vk_post_id,
+                    },
+                    post_datetime: chrono::DateTime::from_timestamp(post.vk_post_timestamp, 0)
+                        .expect("vk_post_timestamp should be correct timestamp"),
+                    sent_steps: post.sent_steps.try_into().unwrap_or_default(),
+                    layout: post
+                        .post_layout
+                        .parse()
+                        .expect("post_layout should be correct layout"),
+                })
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Возвращает сообщения, которыми публикация была переслана в канал, в порядке отправки.
+    pub async fn get_forwarded_messages(
+        &self,
+        channel: ChannelEntryId,
+        post: VkPostId,
+    ) -> Vec<ForwardedMessage> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_messages;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            forwarded_messages::table
+                .filter(forwarded_messages::channel_id.eq(channel.0))
+                .filter(forwarded_messages::vk_owner_id.eq(post.owner_id.0))
+                .filter(forwarded_messages::vk_post_id.eq(post.post_id))
+                .order(forwarded_messages::position.asc())
+                .select(models::ForwardedMessage::as_select())
+                .load(&mut *conn)
+                .expect("database operations should be successful")
+                .into_iter()
+                .map(|message| ForwardedMessage {
+                    message_id: MessageId(message.tg_message_id),
+                    media_id: message.media_id,
+                    text: message.text,
+                    entities: message.entities.as_deref().map(deserialize_entities),
+                })
+                .collect()
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Обновляет вложения и тексты сообщений после правки публикации.
+    pub async fn update_forwarded_messages(
+        &self,
+        tg_channel: TelegramChannelId,
+        messages: &[ForwardedMessage],
+    ) {
+        let messages = messages.to_vec();
+
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_messages;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            conn.transaction(|conn| {
+                for message in messages {
+                    diesel::update(forwarded_messages::table)
+                        .filter(forwarded_messages::tg_chat_id.eq(tg_channel.0))
+                        .filter(forwarded_messages::tg_message_id.eq(message.message_id.0))
+                        .set((
+                            forwarded_messages::media_id.eq(message.media_id),
+                            forwarded_messages::text.eq(message.text),
+                            forwarded_messages::entities
+                                .eq(message.entities.as_deref().map(serialize_entities)),
+                        ))
+                        .execute(conn)?;
+                }
+
+                diesel::QueryResult::Ok(())
+            })
+            .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Запоминает, когда нужно закрыть опрос, отправленный в канал.
+    pub async fn schedule_poll_close(
+        &self,
+        tg_channel: TelegramChannelId,
+        message_id: MessageId,
+        close_datetime: Option<chrono::DateTime<chrono::Utc>>,
+    ) {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_messages;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            diesel::update(forwarded_messages::table)
+                .filter(forwarded_messages::tg_chat_id.eq(tg_channel.0))
+                .filter(forwarded_messages::tg_message_id.eq(message_id.0))
+                .set(
+                    forwarded_messages::poll_close_timestamp
+                        .eq(close_datetime.map(|dt| dt.timestamp())),
+                )
+                .execute(&mut *conn)
+                .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Возвращает сообщения с опросами, которые пора закрыть.
+    pub async fn get_expired_polls(
+        &self,
+        tg_channel: TelegramChannelId,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<MessageId> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_messages;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            forwarded_messages::table
+                .filter(forwarded_messages::tg_chat_id.eq(tg_channel.0))
+                .filter(forwarded_messages::poll_close_timestamp.le(now.timestamp()))
+                .select(forwarded_messages::tg_message_id)
+                .load::<i32>(&mut *conn)
+                .expect("database operations should be successful")
+                .into_iter()
+                .map(MessageId)
+                .collect()
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Отмечает, что удаление публикации во ВКонтакте перенесено в канал.
+    pub async fn mark_post_deleted(&self, channel: ChannelEntryId, post: VkPostId) {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_posts;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            diesel::update(forwarded_posts::table)
+                .filter(forwarded_posts::channel_id.eq(channel.0))
+                .filter(forwarded_posts::vk_owner_id.eq(post.owner_id.0))
+                .filter(forwarded_posts::vk_post_id.eq(post.post_id))
+                .set(forwarded_posts::is_deleted.eq(true))
+                .execute(&mut *conn)
+                .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Возвращает отправленные целиком и не удалённые публикации канала,
+    /// вышедшие во ВКонтакте не раньше `since`.
+    pub async fn get_tracked_posts(
+        &self,
+        channel: ChannelEntryId,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<TrackedPost> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_posts;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            forwarded_posts::table
+                .filter(forwarded_posts::channel_id.eq(channel.0))
+                .filter(forwarded_posts::is_sent.eq(true))
+                .filter(forwarded_posts::is_deleted.eq(false))
+                .filter(forwarded_posts::vk_post_timestamp.ge(since.timestamp()))
+                .order(forwarded_posts::vk_post_id.asc())
+                .select(models::ForwardedPost::as_select())
+                .load(&mut *conn)
+                .expect("database operations should be successful")
+                .into_iter()
+                .map(|post| TrackedPost {
+                    post: VkPostId {
+                        owner_id: VkOwnerId(post.vk_owner_id),
+                        post_id: post.vk_post_id,
+                    },
+                    content_hash: post.content_hash,
+                    layout: post
+                        .post_layout
+                        .parse()
+                        .expect("post_layout should be correct layout"),
+                })
+                .collect()
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Сохраняет хэш содержимого публикации после её правки.
+    pub async fn set_content_hash(&self, channel: ChannelEntryId, post: VkPostId, hash: String) {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_posts;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            diesel::update(forwarded_posts::table)
+                .filter(forwarded_posts::channel_id.eq(channel.0))
+                .filter(forwarded_posts::vk_owner_id.eq(post.owner_id.0))
+                .filter(forwarded_posts::vk_post_id.eq(post.post_id))
+                .set(forwarded_posts::content_hash.eq(hash))
+                .execute(&mut *conn)
+                .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Ищет публикацию ВК, из которой получено сообщение в канале.
+    pub async fn find_forwarded_post(
+        &self,
+        tg_channel: TelegramChannelId,
+        message_id: MessageId,
+    ) -> Option<(ChannelEntryId, VkPostId)> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::forwarded_messages;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            forwarded_messages::table
+                .filter(forwarded_messages::tg_chat_id.eq(tg_channel.0))
+                .filter(forwarded_messages::tg_message_id.eq(message_id.0))
+                .select(models::ForwardedMessage::as_select())
+                .first(&mut *conn)
+                .optional()
+                .expect("database operations should be successful")
+                .map(|message| {
+                    let post = VkPostId {
+                        owner_id: VkOwnerId(message.vk_owner_id),
+                        post_id: message.vk_post_id,
+                    };
+
+                    (ChannelEntryId(message.channel_id), post)
+                })
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    pub async fn get_admins(&self) -> Vec<UserId> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::admins;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            admins::table
+                .select(admins::user_id)
+                .load::<i64>(&mut *conn)
+                .expect("database operations should be successful")
+                .into_iter()
+                .map(|id| UserId(id as u64))
+                .collect()
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    pub async fn is_admin(&self, user_id: UserId) -> bool {
+        let user_id = user_id.0 as i64;
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::admins;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            admins::table
+                .filter(admins::user_id.eq(user_id))
+                .count()
+                .get_result::<i64>(&mut *conn)
+                .expect("database operations should be successful")
+                > 0
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Выдаёт пользователю доступ к боту.
+    /// Возвращает `false`, если доступ уже был выдан.
+    pub async fn add_admin(&self, user_id: UserId, granted_by: UserId) -> bool {
+        let admin = models::Admin {
+            user_id: user_id.0 as i64,
+            granted_by: granted_by.0 as i64,
+            granted_timestamp: chrono::Utc::now().timestamp(),
+        };
+
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::admins;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            diesel::insert_or_ignore_into(admins::table)
+                .values(admin)
+                .execute(&mut *conn)
+                .expect("database operations should be successful")
+                > 0
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Отзывает у пользователя доступ к боту.
+    /// Возвращает `false`, если доступа не было.
+    pub async fn remove_admin(&self, user_id: UserId) -> bool {
+        let user_id = user_id.0 as i64;
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::admins;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            diesel::delete(admins::table)
+                .filter(admins::user_id.eq(user_id))
+                .execute(&mut *conn)
+                .expect("database operations should be successful")
+                > 0
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    /// Возвращает сохранённое состояние диалога и время его последнего изменения.
+    pub async fn get_dialogue_state(
+        &self,
+        chat_id: i64,
+    ) -> Option<(String, chrono::DateTime<chrono::Utc>)> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::dialogue_states;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            dialogue_states::table
+                .filter(dialogue_states::chat_id.eq(chat_id))
+                .select(models::DialogueState::as_select())
+                .first(&mut *conn)
+                .optional()
+                .expect("database operations should be successful")
+                .map(|row| {
+                    let updated = chrono::DateTime::from_timestamp(row.updated_timestamp, 0)
+                        .expect("updated_timestamp should be correct timestamp");
+
+                    (row.state, updated)
+                })
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+
+    pub async fn set_dialogue_state(&self, chat_id: i64, state: String) {
+        let row = models::DialogueState {
+            chat_id,
+            state,
+            updated_timestamp: chrono::Utc::now().timestamp(),
+        };
+
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::dialogue_states;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            diesel::insert_into(dialogue_states::table)
+                .values(&row)
+                .on_conflict(dialogue_states::chat_id)
+                .do_update()
+                .set(&row)
                 .execute(&mut *conn)
                 .expect("database operations should be successful");
         })
         .await
         .expect("database queries shouldn't panic")
     }
+
+    pub async fn remove_dialogue_state(&self, chat_id: i64) {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use schema::dialogue_states;
+
+            let mut conn = conn.lock().expect("connection shouldn't be poisoned");
+
+            diesel::delete(dialogue_states::table)
+                .filter(dialogue_states::chat_id.eq(chat_id))
+                .execute(&mut *conn)
+                .expect("database operations should be successful");
+        })
+        .await
+        .expect("database queries shouldn't panic")
+    }
+}
+
+fn serialize_entities(entities: &[MessageEntity]) -> String {
+    serde_json::to_string(entities).expect("entities should be serializable")
+}
+
+fn deserialize_entities(entities: &str) -> Vec<MessageEntity> {
+    serde_json::from_str(entities).expect("entities should be correct json")
 }
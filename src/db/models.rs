@@ -1,5 +1,6 @@
 use crate::domain;
 use diesel::prelude::*;
+use teloxide::types::UserId;
 
 #[derive(Insertable, AsChangeset)]
 #[diesel(table_name = super::schema::channels)]
@@ -17,8 +18,29 @@ pub struct NewChannel {
     /// Время последней проверки на новые публикации.
     pub last_poll_timestamp: Option<i64>,
 
+    /// Идентификатор последней пересланной публикации на стене.
+    pub last_post_id: Option<i64>,
+
     /// Время публикации последней записи на стене.
     pub last_post_timestamp: Option<i64>,
+
+    /// Числовой идентификатор владельца стены ВК.
+    pub vk_owner_id: Option<i64>,
+
+    /// Идентификатор пользователя Telegram, добавившего канал.
+    pub owner_user_id: Option<i64>,
+
+    /// Что делать с публикацией, отправка которой прервалась.
+    pub partial_post_policy: String,
+
+    /// Сколько секунд после публикации отслеживаются её правки и удаление.
+    pub edit_window_secs: i32,
+
+    /// Что делать с сообщениями, если публикация удалена во ВКонтакте.
+    pub deletion_mode: String,
+
+    /// Как текст публикации располагается относительно вложений.
+    pub post_layout: String,
 }
 
 impl From<domain::ChannelInfo> for NewChannel {
@@ -26,13 +48,63 @@ impl From<domain::ChannelInfo> for NewChannel {
         Self {
             tg_channel_id: info.tg_channel.0,
             vk_public_id: info.vk_public_id.0,
-            poll_interval_secs: info
-                .poll_interval
-                .num_seconds()
-                .try_into()
-                .unwrap_or(i32::MAX),
+            poll_interval_secs: duration_to_secs(info.poll_interval),
             last_poll_timestamp: info.last_poll_datetime.map(|dt| dt.timestamp()),
+            last_post_id: info.last_post_id,
             last_post_timestamp: info.last_post_datetime.map(|dt| dt.timestamp()),
+            vk_owner_id: info.vk_owner_id.map(|id| id.0),
+            owner_user_id: info.owner.map(|id| id.0 as i64),
+            partial_post_policy: info.settings.partial_post_policy.as_str().to_owned(),
+            edit_window_secs: duration_to_secs(info.settings.edit_window),
+            deletion_mode: info.settings.deletion_mode.as_str().to_owned(),
+            post_layout: info.settings.post_layout.as_str().to_owned(),
+        }
+    }
+}
+
+/// Состояние опроса канала, которое обновляет опросчик.
+///
+/// Не содержит настроек, чтобы опросчик не затирал изменения, сделанные владельцем канала.
+#[derive(AsChangeset)]
+#[diesel(table_name = super::schema::channels)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ChannelPollState {
+    pub vk_public_id: String,
+    pub vk_owner_id: Option<i64>,
+    pub last_poll_timestamp: Option<i64>,
+    pub last_post_id: Option<i64>,
+    pub last_post_timestamp: Option<i64>,
+}
+
+impl From<&domain::ChannelInfo> for ChannelPollState {
+    fn from(info: &domain::ChannelInfo) -> Self {
+        Self {
+            vk_public_id: info.vk_public_id.0.clone(),
+            vk_owner_id: info.vk_owner_id.map(|id| id.0),
+            last_poll_timestamp: info.last_poll_datetime.map(|dt| dt.timestamp()),
+            last_post_id: info.last_post_id,
+            last_post_timestamp: info.last_post_datetime.map(|dt| dt.timestamp()),
+        }
+    }
+}
+
+#[derive(AsChangeset)]
+#[diesel(table_name = super::schema::channels)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ChannelSettings {
+    pub partial_post_policy: String,
+    pub edit_window_secs: i32,
+    pub deletion_mode: String,
+    pub post_layout: String,
+}
+
+impl From<&domain::ChannelSettings> for ChannelSettings {
+    fn from(settings: &domain::ChannelSettings) -> Self {
+        Self {
+            partial_post_policy: settings.partial_post_policy.as_str().to_owned(),
+            edit_window_secs: duration_to_secs(settings.edit_window),
+            deletion_mode: settings.deletion_mode.as_str().to_owned(),
+            post_layout: settings.post_layout.as_str().to_owned(),
         }
     }
 }
@@ -55,8 +127,29 @@ pub struct Channel {
     /// Время последней проверки на новые публикации.
     pub last_poll_timestamp: Option<i64>,
 
+    /// Идентификатор последней пересланной публикации на стене.
+    pub last_post_id: Option<i64>,
+
     /// Время публикации последней записи на стене.
     pub last_post_timestamp: Option<i64>,
+
+    /// Числовой идентификатор владельца стены ВК.
+    pub vk_owner_id: Option<i64>,
+
+    /// Идентификатор пользователя Telegram, добавившего канал.
+    pub owner_user_id: Option<i64>,
+
+    /// Что делать с публикацией, отправка которой прервалась.
+    pub partial_post_policy: String,
+
+    /// Сколько секунд после публикации отслеживаются её правки и удаление.
+    pub edit_window_secs: i32,
+
+    /// Что делать с сообщениями, если публикация удалена во ВКонтакте.
+    pub deletion_mode: String,
+
+    /// Как текст публикации располагается относительно вложений.
+    pub post_layout: String,
 }
 
 impl From<Channel> for domain::ChannelInfo {
@@ -64,15 +157,135 @@ impl From<Channel> for domain::ChannelInfo {
         Self {
             tg_channel: domain::TelegramChannelId(ch.tg_channel_id),
             vk_public_id: domain::VkId(ch.vk_public_id),
+            vk_owner_id: ch.vk_owner_id.map(domain::VkOwnerId),
+            owner: ch.owner_user_id.map(|id| UserId(id as u64)),
             poll_interval: chrono::Duration::seconds(ch.poll_interval_secs.into()),
             last_poll_datetime: ch.last_poll_timestamp.map(|ts| {
                 chrono::DateTime::from_timestamp(ts, 0)
                     .expect("last_poll_timestamp should be correct timestamp")
             }),
+            last_post_id: ch.last_post_id,
             last_post_datetime: ch.last_post_timestamp.map(|ts| {
                 chrono::DateTime::from_timestamp(ts, 0)
                     .expect("last_post_timestamp should be correct timestamp")
             }),
+            settings: domain::ChannelSettings {
+                partial_post_policy: ch
+                    .partial_post_policy
+                    .parse()
+                    .expect("partial_post_policy should be correct policy"),
+                edit_window: chrono::Duration::seconds(ch.edit_window_secs.into()),
+                deletion_mode: ch
+                    .deletion_mode
+                    .parse()
+                    .expect("deletion_mode should be correct mode"),
+                post_layout: ch
+                    .post_layout
+                    .parse()
+                    .expect("post_layout should be correct layout"),
+            },
         }
     }
 }
+
+#[derive(Insertable, Queryable, Selectable)]
+#[diesel(table_name = super::schema::admins)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Admin {
+    /// Идентификатор пользователя Telegram, которому выдан доступ к боту.
+    pub user_id: i64,
+
+    /// Идентификатор администратора из конфига, выдавшего доступ.
+    pub granted_by: i64,
+
+    /// Время выдачи доступа.
+    pub granted_timestamp: i64,
+}
+
+#[derive(Insertable, AsChangeset, Queryable, Selectable)]
+#[diesel(table_name = super::schema::dialogue_states)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct DialogueState {
+    /// Идентификатор чата с пользователем.
+    pub chat_id: i64,
+
+    /// Состояние диалога в JSON.
+    pub state: String,
+
+    /// Время последнего изменения состояния.
+    pub updated_timestamp: i64,
+}
+
+#[derive(Insertable, Queryable, Selectable)]
+#[diesel(table_name = super::schema::forwarded_posts)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ForwardedPost {
+    /// Идентификатор записи в таблице channels.
+    pub channel_id: i32,
+
+    /// Идентификатор владельца стены ВК.
+    pub vk_owner_id: i64,
+
+    /// Идентификатор публикации на стене ВК.
+    pub vk_post_id: i64,
+
+    /// Время публикации записи во ВКонтакте.
+    pub vk_post_timestamp: i64,
+
+    /// Время начала пересылки записи в Telegram.
+    pub forwarded_timestamp: i64,
+
+    /// Количество успешно выполненных шагов отправки.
+    pub sent_steps: i32,
+
+    /// Публикация отправлена целиком.
+    pub is_sent: bool,
+
+    /// Хэш текста и вложений публикации на момент последней отправки или правки.
+    pub content_hash: Option<String>,
+
+    /// Публикация удалена во ВКонтакте, и удаление перенесено в Telegram.
+    pub is_deleted: bool,
+
+    /// Расположение текста публикации, с которым она отправлялась.
+    pub post_layout: String,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = super::schema::forwarded_messages)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ForwardedMessage {
+    /// Идентификатор Telegram канала.
+    pub tg_chat_id: i64,
+
+    /// Идентификатор сообщения в канале.
+    pub tg_message_id: i32,
+
+    /// Идентификатор записи в таблице channels.
+    pub channel_id: i32,
+
+    /// Идентификатор владельца стены ВК.
+    pub vk_owner_id: i64,
+
+    /// Идентификатор публикации на стене ВК.
+    pub vk_post_id: i64,
+
+    /// Порядковый номер сообщения среди сообщений публикации.
+    pub position: i32,
+
+    /// Идентификатор вложения ВК, отправленного этим сообщением.
+    pub media_id: Option<String>,
+
+    /// Текст или подпись, с которыми было отправлено сообщение.
+    pub text: Option<String>,
+
+    /// Время, когда опрос в этом сообщении нужно закрыть.
+    pub poll_close_timestamp: Option<i64>,
+
+    /// Разметка текста сообщения в JSON.
+    pub entities: Option<String>,
+}
+
+fn duration_to_secs(duration: chrono::Duration) -> i32 {
+    duration.num_seconds().try_into().unwrap_or(i32::MAX)
+}
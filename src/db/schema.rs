@@ -1,5 +1,13 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    admins (user_id) {
+        user_id -> BigInt,
+        granted_by -> BigInt,
+        granted_timestamp -> BigInt,
+    }
+}
+
 diesel::table! {
     channels (id) {
         id -> Integer,
@@ -9,5 +17,57 @@ diesel::table! {
         last_poll_timestamp -> Nullable<BigInt>,
         last_post_id -> Nullable<BigInt>,
         last_post_timestamp -> Nullable<BigInt>,
+        vk_owner_id -> Nullable<BigInt>,
+        owner_user_id -> Nullable<BigInt>,
+        partial_post_policy -> Text,
+        edit_window_secs -> Integer,
+        deletion_mode -> Text,
+        post_layout -> Text,
     }
 }
+
+diesel::table! {
+    dialogue_states (chat_id) {
+        chat_id -> BigInt,
+        state -> Text,
+        updated_timestamp -> BigInt,
+    }
+}
+
+diesel::table! {
+    forwarded_messages (tg_chat_id, tg_message_id) {
+        tg_chat_id -> BigInt,
+        tg_message_id -> Integer,
+        channel_id -> Integer,
+        vk_owner_id -> BigInt,
+        vk_post_id -> BigInt,
+        position -> Integer,
+        media_id -> Nullable<Text>,
+        text -> Nullable<Text>,
+        poll_close_timestamp -> Nullable<BigInt>,
+        entities -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    forwarded_posts (channel_id, vk_owner_id, vk_post_id) {
+        channel_id -> Integer,
+        vk_owner_id -> BigInt,
+        vk_post_id -> BigInt,
+        vk_post_timestamp -> BigInt,
+        forwarded_timestamp -> BigInt,
+        sent_steps -> Integer,
+        is_sent -> Bool,
+        content_hash -> Nullable<Text>,
+        is_deleted -> Bool,
+        post_layout -> Text,
+    }
+}
+
+diesel::allow_tables_to_appear_in_same_query!(
+    admins,
+    channels,
+    dialogue_states,
+    forwarded_messages,
+    forwarded_posts,
+);
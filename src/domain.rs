@@ -1,34 +1,254 @@
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use teloxide::types::{MessageEntity, MessageEntityKind, MessageId, UserId};
+use url::Url;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct ChannelEntryId(pub i32);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ChannelInfo {
     /// Идентификатор Telegram канала, куда будут отправляться посты.
     pub tg_channel: TelegramChannelId,
 
-    /// Идентификатор стены ВК, откуда будут читаться публикации.
+    /// Короткий адрес стены ВК, откуда будут читаться публикации.
     pub vk_public_id: VkId,
 
+    /// Числовой идентификатор владельца стены ВК.
+    ///
+    /// Отсутствует у каналов, добавленных до появления этого поля.
+    /// Такие каналы получают идентификатор при первом опросе.
+    pub vk_owner_id: Option<VkOwnerId>,
+
     /// Интервал проверки на новые записи в секундах.
+    #[serde(with = "duration_secs")]
     pub poll_interval: chrono::Duration,
 
     /// Время последней проверки на новые публикации.
     pub last_poll_datetime: Option<chrono::DateTime<chrono::Utc>>,
 
+    /// Идентификатор последней пересланной публикации на стене.
+    ///
+    /// Отсутствует у каналов, добавленных до появления этого поля.
+    /// Для них новые публикации определяются по `last_post_datetime`.
+    pub last_post_id: Option<i64>,
+
     /// Время публикации последней записи на стене.
     pub last_post_datetime: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Пользователь Telegram, добавивший канал.
+    ///
+    /// Отсутствует у каналов, добавленных до появления этого поля.
+    /// Такие каналы видят только администраторы из конфига.
+    pub owner: Option<UserId>,
+
+    /// Настройки пересылки.
+    #[serde(default)]
+    pub settings: ChannelSettings,
+}
+
+/// Настройки пересылки, которые владелец канала меняет командой `/settings`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelSettings {
+    /// Что делать с публикацией, отправка которой прервалась на середине.
+    #[serde(default)]
+    pub partial_post_policy: PartialPostPolicy,
+
+    /// Сколько времени после публикации её правки и удаление во ВКонтакте
+    /// переносятся в Telegram. Нулевое значение отключает отслеживание публикаций.
+    #[serde(default = "default_edit_window", with = "duration_secs")]
+    pub edit_window: chrono::Duration,
+
+    /// Что делать с сообщениями, если публикация удалена во ВКонтакте.
+    #[serde(default)]
+    pub deletion_mode: DeletionMode,
+
+    /// Как текст публикации располагается относительно вложений.
+    #[serde(default)]
+    pub post_layout: PostLayout,
+}
+
+impl Default for ChannelSettings {
+    fn default() -> Self {
+        Self {
+            partial_post_policy: PartialPostPolicy::default(),
+            edit_window: default_edit_window(),
+            deletion_mode: DeletionMode::default(),
+            post_layout: PostLayout::default(),
+        }
+    }
+}
+
+fn default_edit_window() -> chrono::Duration {
+    chrono::Duration::days(1)
+}
+
+/// Способ восстановления частично отправленной публикации.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PartialPostPolicy {
+    /// Дослать только недостающие части.
+    #[default]
+    Complete,
+
+    /// Удалить уже отправленные сообщения и отправить публикацию заново.
+    Restart,
 }
 
+impl PartialPostPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Complete => "complete",
+            Self::Restart => "restart",
+        }
+    }
+}
+
+impl std::str::FromStr for PartialPostPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "complete" => Ok(Self::Complete),
+            "restart" => Ok(Self::Restart),
+            _ => Err(anyhow::anyhow!("unknown partial post policy '{s}'")),
+        }
+    }
+}
+
+/// Сообщение в канале, отправленное при пересылке публикации.
 #[derive(Clone, Debug)]
-pub struct VkId(pub String);
+pub struct ForwardedMessage {
+    pub message_id: MessageId,
+
+    /// Идентификатор вложения ВК, например `photo-1_2`, если сообщение содержит вложение.
+    pub media_id: Option<String>,
 
-impl From<url::Url> for VkId {
-    fn from(url: url::Url) -> Self {
-        // Первый символ всегда /
-        VkId(url.path().chars().skip(1).collect::<String>())
+    /// Текст сообщения или подпись к вложению, с которыми оно было отправлено.
+    ///
+    /// Отсутствует у сообщений, отправленных до появления этого поля.
+    pub text: Option<String>,
+
+    /// Разметка текста сообщения или подписи.
+    ///
+    /// Отсутствует у сообщений, отправленных до перехода на сущности: их текст
+    /// размечен MarkdownV2, а подписи к вложениям не размечены.
+    pub entities: Option<Vec<MessageEntity>>,
+}
+
+impl ForwardedMessage {
+    /// Является ли сообщение опросом. Опросы нельзя редактировать.
+    pub fn is_poll(&self) -> bool {
+        self.media_id
+            .as_deref()
+            .is_some_and(|media_id| media_id.starts_with("poll"))
     }
 }
 
+/// Пересланная публикация, правки которой отслеживаются.
+#[derive(Clone, Debug)]
+pub struct TrackedPost {
+    pub post: VkPostId,
+
+    /// Хэш содержимого публикации на момент последней отправки или правки.
+    ///
+    /// Отсутствует у публикаций, пересланных до появления синхронизации правок.
+    pub content_hash: Option<String>,
+
+    /// Расположение, с которым публикация была отправлена.
+    pub layout: PostLayout,
+}
+
+/// Способ переноса удаления публикации во ВКонтакте в канал.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeletionMode {
+    /// Не отслеживать удаление.
+    #[default]
+    Off,
+
+    /// Удалить сообщения из канала.
+    Delete,
+
+    /// Оставить сообщения, добавив пометку об удалении в источнике.
+    Mark,
+}
+
+impl DeletionMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Delete => "delete",
+            Self::Mark => "mark",
+        }
+    }
+}
+
+impl std::str::FromStr for DeletionMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "delete" => Ok(Self::Delete),
+            "mark" => Ok(Self::Mark),
+            _ => Err(anyhow::anyhow!("unknown deletion mode '{s}'")),
+        }
+    }
+}
+
+/// Расположение текста публикации относительно вложений.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PostLayout {
+    /// Текст отправляется отдельным сообщением перед вложениями.
+    #[default]
+    Separate,
+
+    /// Короткий текст становится подписью к первому вложению,
+    /// и публикация выглядит в канале одним постом.
+    Compact,
+}
+
+impl PostLayout {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Separate => "separate",
+            Self::Compact => "compact",
+        }
+    }
+}
+
+impl std::str::FromStr for PostLayout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "separate" => Ok(Self::Separate),
+            "compact" => Ok(Self::Compact),
+            _ => Err(anyhow::anyhow!("unknown post layout '{s}'")),
+        }
+    }
+}
+
+/// Публикация, отправка которой в канал была прервана.
+#[derive(Clone, Copy, Debug)]
+pub struct UnfinishedPost {
+    pub post: VkPostId,
+
+    /// Время публикации записи во ВКонтакте.
+    pub post_datetime: chrono::DateTime<chrono::Utc>,
+
+    /// Количество успешно выполненных шагов отправки.
+    pub sent_steps: usize,
+
+    /// Расположение, с которым публикация начала отправляться.
+    pub layout: PostLayout,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VkId(pub String);
+
 impl std::fmt::Display for VkId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str("https://vk.com/")?;
@@ -36,7 +256,113 @@ impl std::fmt::Display for VkId {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+/// Идентификатор владельца стены ВК.
+/// Положительный у пользователей, отрицательный у сообществ.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VkOwnerId(pub i64);
+
+/// Публикация на стене ВК.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VkPostId {
+    pub owner_id: VkOwnerId,
+    pub post_id: i64,
+}
+
+impl std::fmt::Display for VkPostId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "https://vk.com/wall{}_{}", self.owner_id.0, self.post_id)
+    }
+}
+
+/// Адрес стены ВК в том виде, в котором его ввёл пользователь.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VkWallAddress {
+    /// Числовой идентификатор: `-123`, `club123`, `public123`, `event123` или `id42`.
+    OwnerId(VkOwnerId),
+
+    /// Короткий адрес, который нужно разрешить через `utils.resolveScreenName`.
+    ScreenName(String),
+}
+
+impl VkWallAddress {
+    /// Разбирает ссылку на стену или короткий адрес.
+    ///
+    /// Поддерживаются ссылки на `vk.com` и `vk.ru`, в том числе мобильные,
+    /// ссылки без схемы, а также просто короткие адреса и числовые идентификаторы.
+    /// Параметры ссылки, например `?w=wall-1_2`, игнорируются.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+
+        let segment = match Url::parse(text) {
+            Ok(url) => {
+                if !matches!(url.scheme(), "http" | "https") || !is_vk_host(url.host_str()?) {
+                    return None;
+                }
+
+                url.path_segments()?.next()?.to_owned()
+            }
+            Err(_) => match text.split_once('/') {
+                Some((host, path)) => {
+                    if !is_vk_host(host) {
+                        return None;
+                    }
+
+                    path.split(['/', '?', '#']).next()?.to_owned()
+                }
+                // Адрес самого ВКонтакте без пути не указывает на стену.
+                None if is_vk_host(text) => return None,
+                None => text.strip_prefix('@').unwrap_or(text).to_owned(),
+            },
+        };
+
+        Self::from_path_segment(&segment)
+    }
+
+    fn from_path_segment(segment: &str) -> Option<Self> {
+        static OWNER_ID_REGEX: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"^(club|public|event|id)(\d+)$").unwrap());
+
+        static SCREEN_NAME_REGEX: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"^[A-Za-z0-9_.]+$").unwrap());
+
+        if let Ok(id) = segment.parse::<i64>() {
+            return (id != 0).then_some(Self::OwnerId(VkOwnerId(id)));
+        }
+
+        if let Some(captures) = OWNER_ID_REGEX.captures(segment) {
+            let id = captures[2].parse::<i64>().ok().filter(|id| *id != 0)?;
+
+            return Some(Self::OwnerId(VkOwnerId(match &captures[1] {
+                "id" => id,
+                _ => -id,
+            })));
+        }
+
+        SCREEN_NAME_REGEX
+            .is_match(segment)
+            .then(|| Self::ScreenName(segment.to_owned()))
+    }
+
+    /// Возвращает короткий адрес, по которому стена открывается во ВКонтакте.
+    pub fn to_vk_id(&self) -> VkId {
+        match self {
+            Self::OwnerId(VkOwnerId(id)) if *id < 0 => VkId(format!("club{}", -id)),
+            Self::OwnerId(VkOwnerId(id)) => VkId(format!("id{id}")),
+            Self::ScreenName(name) => VkId(name.clone()),
+        }
+    }
+}
+
+fn is_vk_host(host: &str) -> bool {
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let host = host.strip_prefix("m.").unwrap_or(host);
+
+    matches!(host, "vk.com" | "vk.ru" | "vkontakte.ru")
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct TelegramChannelId(pub i64);
 
 impl std::fmt::Display for TelegramChannelId {
@@ -49,15 +375,201 @@ impl std::fmt::Display for TelegramChannelId {
     }
 }
 
+/// Сериализация `chrono::Duration` в целое количество секунд.
+mod duration_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &chrono::Duration, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_i64(duration.num_seconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<chrono::Duration, D::Error> {
+        i64::deserialize(d).map(chrono::Duration::seconds)
+    }
+}
+
 pub struct TelegramPost {
     pub channel_id: TelegramChannelId,
-    pub text: String,
-    pub photos: Vec<TelegramPostPhoto>,
+    pub text: TelegramText,
+    pub media: Vec<TelegramPostMedia>,
+    pub poll: Option<TelegramPoll>,
     pub is_pinned: bool,
+
+    /// Показывать превью ссылки под текстом публикации.
+    pub link_preview: bool,
+
+    pub layout: PostLayout,
+}
+
+/// Текст сообщения Telegram с разметкой в виде сущностей.
+///
+/// Telegram не разбирает такой текст, поэтому экранировать в нём ничего не нужно.
+/// Смещения сущностей считаются в UTF-16, как того требует Telegram.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TelegramText {
+    pub text: String,
+    pub entities: Vec<MessageEntity>,
+}
+
+impl TelegramText {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            entities: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Добавляет текст без разметки.
+    pub fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    /// Добавляет текст, размеченный сущностью `kind`.
+    pub fn push_entity(&mut self, text: &str, kind: MessageEntityKind) {
+        let length = utf16_len(text);
+
+        if length > 0 {
+            self.entities.push(MessageEntity {
+                kind,
+                offset: utf16_len(&self.text),
+                length,
+            });
+        }
+
+        self.text.push_str(text);
+    }
+
+    /// Добавляет другой текст вместе с его разметкой.
+    pub fn append(&mut self, other: TelegramText) {
+        let offset = utf16_len(&self.text);
+
+        self.entities
+            .extend(other.entities.into_iter().map(|entity| MessageEntity {
+                offset: entity.offset + offset,
+                ..entity
+            }));
+
+        self.text.push_str(&other.text);
+    }
+
+    /// Объединяет тексты через разделитель.
+    pub fn join(parts: impl IntoIterator<Item = TelegramText>, separator: &str) -> Self {
+        let mut joined = Self::default();
+
+        for (n, part) in parts.into_iter().enumerate() {
+            if n > 0 {
+                joined.push(separator);
+            }
+
+            joined.append(part);
+        }
+
+        joined
+    }
 }
 
+/// Длина текста в кодовых единицах UTF-16.
+pub fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// Опрос, отправляемый в канал после текста и вложений публикации.
 #[derive(Clone)]
-pub struct TelegramPostPhoto {
+pub struct TelegramPoll {
+    /// Идентификатор опроса ВК, например `poll-1_2`.
+    pub id: String,
+    pub question: String,
+    pub options: Vec<String>,
+    pub is_anonymous: bool,
+    pub allows_multiple_answers: bool,
+    pub is_closed: bool,
+
+    /// Время завершения опроса во ВКонтакте.
+    pub close_datetime: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Вложение публикации, загружаемое в Telegram файлом.
+#[derive(Clone)]
+pub struct TelegramPostMedia {
+    /// Идентификатор вложения ВК, например `photo-1_2` или `video-1_2`.
+    pub id: String,
+    pub kind: TelegramMediaKind,
     pub bytes: Vec<u8>,
-    pub description: String,
+
+    /// Имя файла, под которым вложение увидят подписчики.
+    pub file_name: Option<String>,
+    pub description: TelegramText,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TelegramMediaKind {
+    Photo,
+    Video,
+    Document,
+    Animation,
+}
+
+impl TelegramMediaKind {
+    /// Вид альбома, в который Telegram позволяет собрать вложение.
+    ///
+    /// Фотографии и видео объединяются в общий альбом, документы — только друг с другом,
+    /// а анимации отправляются по одной.
+    pub fn album(&self) -> Option<u8> {
+        match self {
+            Self::Photo | Self::Video => Some(0),
+            Self::Document => Some(1),
+            Self::Animation => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_vk_wall_addresses() {
+        let owner = |id| Some(VkWallAddress::OwnerId(VkOwnerId(id)));
+        let name = |name: &str| Some(VkWallAddress::ScreenName(name.to_owned()));
+
+        let cases = [
+            ("https://vk.com/club123", owner(-123)),
+            ("vk.com/club123", owner(-123)),
+            ("https://m.vk.com/name", name("name")),
+            ("m.vk.com/name", name("name")),
+            ("https://vk.ru/name", name("name")),
+            ("vk.ru/name", name("name")),
+            ("https://www.vk.com/public123?w=wall-123_45", owner(-123)),
+            ("vk.com/public123?w=wall-123_45", owner(-123)),
+            ("https://vk.com/id42", owner(42)),
+            ("vk.com/id42", owner(42)),
+            ("https://vk.com/event7/", owner(-7)),
+            ("name", name("name")),
+            ("@name", name("name")),
+            ("  durov  ", name("durov")),
+            ("club123", owner(-123)),
+            ("-123", owner(-123)),
+            ("42", owner(42)),
+            ("https://example.com/x", None),
+            ("example.com/x", None),
+            ("ftp://vk.com/name", None),
+            ("vk.com/", None),
+            ("https://vk.com/", None),
+            ("vk.com", None),
+            ("m.vk.com", None),
+            ("club0", None),
+            ("id0", None),
+            ("0", None),
+            ("", None),
+            ("имя", None),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(VkWallAddress::parse(text), expected, "address: {text:?}");
+        }
+    }
 }
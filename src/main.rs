@@ -102,6 +102,13 @@ async fn run(args: Args) -> anyhow::Result<()> {
 
     let bot = teloxide::Bot::new(&config.telegram.bot_token);
 
+    let access = bot::Access::new(&config.telegram.admins, db.clone());
+
+    let dialogue_storage = bot::DbStorage::new(
+        db.clone(),
+        chrono::Duration::seconds(config.telegram.dialogue_timeout_secs.into()),
+    );
+
     let poller_manager = vk_poller::VkPollManager::new(
         config,
         db,
@@ -113,6 +120,8 @@ async fn run(args: Args) -> anyhow::Result<()> {
     tracker.spawn(bot::run_dialogue(
         bot.clone(),
         poller_manager.clone(),
+        access,
+        dialogue_storage,
         cancellation_token.clone(),
     ));
 
@@ -2,30 +2,72 @@ use chrono::{serde::ts_seconds, DateTime, Utc};
 use serde::{Deserialize, Deserializer};
 use url::Url;
 
-//
-#[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "lowercase", remote = "Self")]
+/// Вложение публикации, взятое из [https://dev.vk.com/ru/reference/objects/attachments-wall].
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Attachment {
     Photo(Photo),
     PhotosList(PhotosList),
     Album(Album),
     Video(Video),
+    Doc(Doc),
+    Graffiti(Graffiti),
+    Audio(Audio),
+    Podcast(Podcast),
+    Poll(Poll),
+    Link(Link),
     Event(Event),
+
+    /// Вложение, которое сервис пока не поддерживает. Содержит его тип.
+    Unsupported(String),
 }
 
-// Хак из ишью https://github.com/serde-rs/serde/issues/1343.
-// TODO: Сохранять информацию о неподдерживаемом вложении.
+// Вложение приходит в виде `{"type": "photo", "photo": {...}}`. Тип разбирается вручную,
+// чтобы неизвестное или неожиданно устроенное вложение не ломало разбор всей публикации.
 impl<'de> Deserialize<'de> for Attachment {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
         #[derive(Deserialize)]
         struct Wrapper {
-            #[serde(rename = "type")]
-            _ignore: String,
-            #[serde(flatten, with = "Attachment")]
-            inner: Attachment,
+            r#type: String,
+            #[serde(flatten)]
+            fields: serde_json::Map<String, serde_json::Value>,
+        }
+
+        fn parse<T: serde::de::DeserializeOwned>(
+            fields: &mut serde_json::Map<String, serde_json::Value>,
+            r#type: &str,
+        ) -> serde_json::Result<T> {
+            let value = fields
+                .remove(r#type)
+                .ok_or_else(|| serde_json::Error::missing_field("attachment body"))?;
+
+            serde_json::from_value(value)
         }
 
-        Wrapper::deserialize(deserializer).map(|w| w.inner)
+        let Wrapper { r#type, mut fields } = Wrapper::deserialize(deserializer)?;
+        let fields = &mut fields;
+
+        let parsed = match r#type.as_str() {
+            "photo" => parse(fields, &r#type).map(Self::Photo),
+            "photos_list" => parse(fields, &r#type).map(Self::PhotosList),
+            "album" => parse(fields, &r#type).map(Self::Album),
+            "video" => parse(fields, &r#type).map(Self::Video),
+            "doc" => parse(fields, &r#type).map(Self::Doc),
+            "graffiti" => parse(fields, &r#type).map(Self::Graffiti),
+            "audio" => parse(fields, &r#type).map(Self::Audio),
+            "podcast" => parse(fields, &r#type).map(Self::Podcast),
+            "poll" => parse(fields, &r#type).map(Self::Poll),
+            "link" => parse(fields, &r#type).map(Self::Link),
+            "event" => parse(fields, &r#type).map(Self::Event),
+            _ => return Ok(Self::Unsupported(r#type)),
+        };
+
+        Ok(parsed.unwrap_or_else(|err| {
+            log::warn!("Failed to parse VK attachment of type '{type}': {err}", type = r#type);
+            Self::Unsupported(r#type)
+        }))
     }
 }
 
@@ -125,6 +167,24 @@ pub enum PhotoType {
 
     /// Пропорциональная копия изображения с максимальным размером 2560x2048px.
     W,
+
+    /// Размеры, которые встречаются у обложек ссылок и других вложений.
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl Photo {
+    /// Самая большая копия изображения.
+    pub fn largest_size(&self) -> Option<&PhotoSize> {
+        self.sizes
+            .iter()
+            .find(|size| size.r#type == PhotoType::W)
+            .or_else(|| {
+                self.sizes
+                    .iter()
+                    .max_by_key(|size| size.width * size.height)
+            })
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -137,12 +197,566 @@ pub struct Album {
     // TODO
 }
 
+/// Видеозапись, взятая из [https://dev.vk.com/ru/reference/objects/video].
 #[derive(Clone, Debug, Deserialize)]
 pub struct Video {
-    // TODO
+    /// Идентификатор видеозаписи.
+    pub id: i64,
+
+    /// Идентификатор владельца видеозаписи.
+    pub owner_id: i64,
+
+    /// Название видеозаписи.
+    #[serde(default)]
+    pub title: String,
+
+    /// Текст описания видеозаписи.
+    #[serde(default)]
+    pub description: String,
+
+    /// Длительность ролика в секундах. Отсутствует у трансляций.
+    pub duration: Option<i64>,
+
+    /// Обложки видеозаписи в разных размерах.
+    #[serde(default)]
+    pub image: Vec<VideoImage>,
+
+    /// Ключ доступа к видеозаписи.
+    pub access_key: Option<String>,
+
+    /// Название платформы, если видеозапись добавлена с внешнего сайта, например `YouTube`.
+    pub platform: Option<String>,
+
+    /// Ссылки на файлы видеозаписи. Приходят не всегда и не для всех ключей доступа.
+    pub files: Option<VideoFiles>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VideoImage {
+    /// Ссылка на обложку.
+    pub url: Url,
+
+    /// Ширина обложки в пикселях.
+    pub width: i64,
+
+    /// Высота обложки в пикселях.
+    pub height: i64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VideoFiles {
+    pub mp4_240: Option<Url>,
+    pub mp4_360: Option<Url>,
+    pub mp4_480: Option<Url>,
+    pub mp4_720: Option<Url>,
+    pub mp4_1080: Option<Url>,
+
+    /// Ссылка на видеозапись на внешнем сайте.
+    pub external: Option<Url>,
+}
+
+impl Video {
+    /// Ссылка на видеозапись во ВКонтакте.
+    pub fn url(&self) -> String {
+        format!("https://vk.com/video{}_{}", self.owner_id, self.id)
+    }
+
+    /// Обложка наибольшего размера.
+    pub fn largest_image(&self) -> Option<&VideoImage> {
+        self.image
+            .iter()
+            .max_by_key(|image| image.width * image.height)
+    }
+
+    /// Ссылки на файлы видеозаписи, от лучшего качества к худшему.
+    pub fn file_urls(&self) -> Vec<&Url> {
+        let Some(files) = &self.files else {
+            return Vec::new();
+        };
+
+        [
+            &files.mp4_1080,
+            &files.mp4_720,
+            &files.mp4_480,
+            &files.mp4_360,
+            &files.mp4_240,
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+/// Документ, взятый из [https://dev.vk.com/ru/reference/objects/doc].
+#[derive(Clone, Debug, Deserialize)]
+pub struct Doc {
+    /// Идентификатор документа.
+    pub id: i64,
+
+    /// Идентификатор владельца документа.
+    pub owner_id: i64,
+
+    /// Название документа.
+    pub title: String,
+
+    /// Размер документа в байтах.
+    pub size: u64,
+
+    /// Расширение документа.
+    pub ext: String,
+
+    /// Адрес документа, по которому его можно загрузить.
+    pub url: Option<Url>,
+
+    /// Тип документа.
+    pub r#type: DocType,
+
+    /// Информация для предварительного просмотра документа.
+    pub preview: Option<DocPreview>,
+}
+
+/// Тип документа.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(from = "u8")]
+pub enum DocType {
+    Text,
+    Archive,
+    Gif,
+    Image,
+    Audio,
+    Video,
+    Ebook,
+    Unknown,
+}
+
+impl From<u8> for DocType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Text,
+            2 => Self::Archive,
+            3 => Self::Gif,
+            4 => Self::Image,
+            5 => Self::Audio,
+            6 => Self::Video,
+            7 => Self::Ebook,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DocPreview {
+    /// Изображения для предпросмотра.
+    pub photo: Option<DocPreviewPhoto>,
+
+    /// Данные о граффити.
+    pub graffiti: Option<DocPreviewGraffiti>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DocPreviewPhoto {
+    pub sizes: Vec<DocPreviewPhotoSize>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DocPreviewPhotoSize {
+    /// Ссылка на копию изображения.
+    pub src: Url,
+
+    /// Ширина копии в пикселях.
+    pub width: i64,
+
+    /// Высота копии в пикселях.
+    pub height: i64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DocPreviewGraffiti {
+    /// Ссылка на изображение граффити.
+    pub src: Url,
+
+    /// Ширина изображения в пикселях.
+    pub width: i64,
+
+    /// Высота изображения в пикселях.
+    pub height: i64,
+}
+
+impl Doc {
+    /// Ссылка на документ во ВКонтакте.
+    pub fn page_url(&self) -> String {
+        format!("https://vk.com/doc{}_{}", self.owner_id, self.id)
+    }
+
+    /// Имя файла с расширением, под которым документ был загружен.
+    pub fn file_name(&self) -> String {
+        let suffix = format!(".{}", self.ext);
+
+        if self.ext.is_empty() || self.title.to_lowercase().ends_with(&suffix.to_lowercase()) {
+            self.title.clone()
+        } else {
+            format!("{}{suffix}", self.title)
+        }
+    }
+}
+
+/// Граффити, взятое из [https://dev.vk.com/ru/reference/objects/graffiti].
+#[derive(Clone, Debug, Deserialize)]
+pub struct Graffiti {
+    /// Идентификатор граффити.
+    pub id: i64,
+
+    /// Идентификатор автора граффити.
+    pub owner_id: i64,
+
+    /// Ссылка на изображение граффити.
+    pub url: Url,
+
+    /// Ширина изображения в пикселях.
+    pub width: i64,
+
+    /// Высота изображения в пикселях.
+    pub height: i64,
+}
+
+/// Аудиозапись, взятая из [https://dev.vk.com/ru/reference/objects/audio].
+///
+/// Ссылка на файл аудиозаписи недоступна с сервисным ключом, поэтому она не разбирается.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Audio {
+    /// Идентификатор аудиозаписи.
+    pub id: i64,
+
+    /// Идентификатор владельца аудиозаписи.
+    pub owner_id: i64,
+
+    /// Исполнитель.
+    pub artist: String,
+
+    /// Название композиции.
+    pub title: String,
+
+    /// Длительность аудиозаписи в секундах.
+    pub duration: i64,
+}
+
+/// Эпизод подкаста, взятый из [https://dev.vk.com/ru/reference/objects/podcast].
+#[derive(Clone, Debug, Deserialize)]
+pub struct Podcast {
+    /// Идентификатор эпизода.
+    pub id: i64,
+
+    /// Идентификатор владельца подкаста.
+    pub owner_id: i64,
+
+    /// Название подкаста.
+    pub artist: String,
+
+    /// Название эпизода.
+    pub title: String,
+
+    /// Длительность эпизода в секундах.
+    pub duration: i64,
+}
+
+impl Podcast {
+    /// Ссылка на страницу эпизода во ВКонтакте.
+    pub fn url(&self) -> String {
+        format!("https://vk.com/podcast{}_{}", self.owner_id, self.id)
+    }
+}
+
+/// Опрос, взятый из [https://dev.vk.com/ru/reference/objects/poll].
+#[derive(Clone, Debug, Deserialize)]
+pub struct Poll {
+    /// Идентификатор опроса.
+    pub id: i64,
+
+    /// Идентификатор владельца опроса.
+    pub owner_id: i64,
+
+    /// Текст вопроса.
+    pub question: String,
+
+    /// Варианты ответа.
+    pub answers: Vec<PollAnswer>,
+
+    /// Можно выбрать несколько вариантов ответа.
+    pub multiple: bool,
+
+    /// Опрос анонимный.
+    pub anonymous: bool,
+
+    /// Время завершения опроса, `0` для бессрочных опросов.
+    pub end_date: i64,
+
+    /// Опрос завершён.
+    pub closed: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PollAnswer {
+    /// Идентификатор варианта ответа.
+    pub id: i64,
+
+    /// Текст варианта ответа.
+    pub text: String,
+}
+
+impl Poll {
+    /// Время завершения опроса, если оно задано.
+    pub fn end_datetime(&self) -> Option<DateTime<Utc>> {
+        match self.end_date {
+            0 => None,
+            ts => DateTime::from_timestamp(ts, 0),
+        }
+    }
+}
+
+/// Ссылка, взятая из [https://dev.vk.com/ru/reference/objects/link].
+#[derive(Clone, Debug, Deserialize)]
+pub struct Link {
+    /// Адрес ссылки.
+    pub url: String,
+
+    /// Заголовок ссылки.
+    #[serde(default)]
+    pub title: String,
+
+    /// Подпись ссылки, обычно адрес сайта.
+    pub caption: Option<String>,
+
+    /// Описание ссылки.
+    pub description: Option<String>,
+
+    /// Изображение превью.
+    pub photo: Option<Photo>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Event {
     // TODO
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(value: serde_json::Value) -> Attachment {
+        serde_json::from_value(value).expect("attachment should always be parsed")
+    }
+
+    #[test]
+    fn parses_video() {
+        let attachment = parse(json!({
+            "type": "video",
+            "video": {
+                "id": 456239017,
+                "owner_id": -1,
+                "title": "Ролик",
+                "description": "Описание",
+                "duration": 125,
+                "image": [
+                    { "url": "https://sun9-1.userapi.com/small.jpg", "width": 130, "height": 96 },
+                    { "url": "https://sun9-1.userapi.com/big.jpg", "width": 800, "height": 450 }
+                ],
+                "access_key": "0123abcd",
+                "files": {
+                    "mp4_360": "https://vkvd1.okcdn.ru/360.mp4",
+                    "mp4_720": "https://vkvd1.okcdn.ru/720.mp4"
+                },
+                "date": 1716120000,
+                "views": 10
+            }
+        }));
+
+        let Attachment::Video(video) = attachment else {
+            panic!("expected video, got {attachment:?}");
+        };
+
+        assert_eq!(video.url(), "https://vk.com/video-1_456239017");
+        assert_eq!(video.duration, Some(125));
+        assert_eq!(video.largest_image().unwrap().width, 800);
+        assert_eq!(
+            video.file_urls(),
+            [
+                &Url::parse("https://vkvd1.okcdn.ru/720.mp4").unwrap(),
+                &Url::parse("https://vkvd1.okcdn.ru/360.mp4").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_doc() {
+        let attachment = parse(json!({
+            "type": "doc",
+            "doc": {
+                "id": 672108813,
+                "owner_id": 1,
+                "title": "animation",
+                "size": 1048576,
+                "ext": "gif",
+                "url": "https://vk.com/doc1_672108813?hash=abc",
+                "date": 1716120000,
+                "type": 3,
+                "preview": {
+                    "photo": {
+                        "sizes": [
+                            { "src": "https://sun9-2.userapi.com/preview.jpg", "width": 100, "height": 75, "type": "s" }
+                        ]
+                    }
+                }
+            }
+        }));
+
+        let Attachment::Doc(doc) = attachment else {
+            panic!("expected doc, got {attachment:?}");
+        };
+
+        assert_eq!(doc.r#type, DocType::Gif);
+        assert_eq!(doc.file_name(), "animation.gif");
+        assert_eq!(doc.page_url(), "https://vk.com/doc1_672108813");
+        assert!(doc.preview.unwrap().photo.is_some());
+    }
+
+    #[test]
+    fn parses_audio() {
+        let attachment = parse(json!({
+            "type": "audio",
+            "audio": {
+                "id": 456239018,
+                "owner_id": 2000000001,
+                "artist": "Исполнитель",
+                "title": "Песня",
+                "duration": 213,
+                "url": "",
+                "date": 1716120000
+            }
+        }));
+
+        let Attachment::Audio(audio) = attachment else {
+            panic!("expected audio, got {attachment:?}");
+        };
+
+        assert_eq!(audio.artist, "Исполнитель");
+        assert_eq!(audio.title, "Песня");
+        assert_eq!(audio.duration, 213);
+    }
+
+    #[test]
+    fn parses_podcast() {
+        let attachment = parse(json!({
+            "type": "podcast",
+            "podcast": {
+                "id": 456239030,
+                "owner_id": -1,
+                "artist": "Подкаст",
+                "title": "Выпуск 1",
+                "duration": 3600,
+                "url": "",
+                "podcast_info": { "plays": 100 }
+            }
+        }));
+
+        let Attachment::Podcast(podcast) = attachment else {
+            panic!("expected podcast, got {attachment:?}");
+        };
+
+        assert_eq!(podcast.url(), "https://vk.com/podcast-1_456239030");
+        assert_eq!(podcast.title, "Выпуск 1");
+    }
+
+    #[test]
+    fn parses_poll() {
+        let attachment = parse(json!({
+            "type": "poll",
+            "poll": {
+                "id": 123,
+                "owner_id": -1,
+                "created": 1716120000,
+                "question": "Вопрос?",
+                "votes": 2,
+                "answers": [
+                    { "id": 1, "text": "Да", "votes": 1, "rate": 50.0 },
+                    { "id": 2, "text": "Нет", "votes": 1, "rate": 50.0 }
+                ],
+                "anonymous": true,
+                "multiple": false,
+                "end_date": 1716206400,
+                "closed": false,
+                "can_vote": true
+            }
+        }));
+
+        let Attachment::Poll(poll) = attachment else {
+            panic!("expected poll, got {attachment:?}");
+        };
+
+        assert_eq!(poll.question, "Вопрос?");
+        assert_eq!(poll.answers.len(), 2);
+        assert!(poll.anonymous);
+        assert_eq!(poll.end_datetime(), DateTime::from_timestamp(1716206400, 0));
+    }
+
+    #[test]
+    fn parses_link() {
+        let attachment = parse(json!({
+            "type": "link",
+            "link": {
+                "url": "https://example.com/article",
+                "title": "Статья",
+                "caption": "example.com",
+                "description": "",
+                "photo": {
+                    "id": 457239017,
+                    "album_id": -26,
+                    "owner_id": 2000000001,
+                    "text": "",
+                    "date": 1716120000,
+                    "sizes": [
+                        { "url": "https://sun9-3.userapi.com/l.jpg", "width": 537, "height": 240, "type": "l" },
+                        { "url": "https://sun9-3.userapi.com/k.jpg", "width": 1074, "height": 480, "type": "k" }
+                    ]
+                }
+            }
+        }));
+
+        let Attachment::Link(link) = attachment else {
+            panic!("expected link, got {attachment:?}");
+        };
+
+        assert_eq!(link.url, "https://example.com/article");
+        assert_eq!(link.title, "Статья");
+        assert_eq!(link.photo.unwrap().largest_size().unwrap().width, 1074);
+    }
+
+    #[test]
+    fn unknown_attachment_is_unsupported() {
+        let attachment = parse(json!({
+            "type": "market",
+            "market": { "id": 1 }
+        }));
+
+        assert!(matches!(attachment, Attachment::Unsupported(r#type) if r#type == "market"));
+    }
+
+    #[test]
+    fn malformed_attachment_is_unsupported() {
+        let malformed = [
+            json!({ "type": "audio", "audio": { "id": 1, "owner_id": 1, "title": "Песня", "duration": 1 } }),
+            json!({ "type": "poll", "poll": { "id": 1, "owner_id": 1, "question": "?", "answers": [], "multiple": false, "anonymous": false } }),
+            json!({ "type": "link", "link": { "url": "https://example.com", "photo": { "id": 1, "owner_id": 1, "text": "", "sizes": [] } } }),
+            json!({ "type": "video" }),
+        ];
+
+        for value in malformed {
+            let r#type = value["type"].as_str().unwrap().to_owned();
+
+            assert!(
+                matches!(&parse(value), Attachment::Unsupported(parsed) if *parsed == r#type),
+                "malformed {type} should be unsupported"
+            );
+        }
+    }
+}
@@ -1,22 +1,63 @@
-use crate::domain::VkId;
+use super::{limiter::RateLimiter, VkError};
+use crate::domain::{VkOwnerId, VkPostId};
 use anyhow::Context;
 use chrono::Utc;
 use serde::Serialize;
-use std::path::PathBuf;
-use tokio::fs;
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    path::PathBuf,
+    time::Duration,
+};
+use tokio::{fs, time::sleep};
 use url::Url;
 
 const SERVER: &str = "https://api.vk.com";
 const VERSION: &str = "5.137";
 
+/// Максимальное количество вызовов методов API внутри одного `execute`.
+pub const MAX_EXECUTE_CALLS: usize = 25;
+
+/// Максимальное количество публикаций в одном запросе `wall.getById`.
+pub const MAX_POSTS_BY_ID: usize = 100;
+
 /// Клиент для работы с API ВКонтакте.
+///
+/// Один клиент разделяется между всеми опросчиками, поэтому ограничение
+/// частоты запросов действует на весь сервисный ключ.
 pub struct Client {
     client: reqwest::Client,
     token: String,
     language: String,
+    limiter: RateLimiter,
+    retries: ClientRetries,
     debug: Option<ClientDebug>,
 }
 
+/// Ограничение частоты запросов к ВК.
+pub struct ClientRateLimit {
+    /// Количество запросов в секунду.
+    pub requests_per_second: u32,
+
+    /// Количество запросов, которые можно выполнить разом после простоя.
+    pub burst: u32,
+}
+
+/// Параметры повторных попыток вызова методов.
+///
+/// Повторяются только временные ошибки: сетевые, ответы 5xx
+/// и превышение количества запросов.
+pub struct ClientRetries {
+    /// Максимальное количество попыток, включая первую.
+    pub max_attempts: u32,
+
+    /// Задержка перед второй попыткой. Каждая следующая увеличивается вдвое.
+    pub initial_delay: Duration,
+
+    /// Максимальная задержка между попытками.
+    pub max_delay: Duration,
+}
+
 /// Параметры отладки клиента.
 pub struct ClientDebug {
     /// Флаг сохранения ответов в `responses_dir_path`.
@@ -29,6 +70,19 @@ pub struct ClientDebug {
     pub responses_dir_path: PathBuf,
 }
 
+/// Параметры одного вызова `wall.get` в пакетном запросе.
+#[derive(Clone, Debug)]
+pub struct WallRequest {
+    /// Идентификатор владельца стены.
+    pub owner_id: VkOwnerId,
+
+    /// Смещение, необходимое для выборки определённого подмножества записей.
+    pub offset: usize,
+
+    /// Количество записей, которое необходимо получить. Максимальное значение: 100.
+    pub count: usize,
+}
+
 #[derive(Serialize)]
 struct MethodParams<'a, P> {
     #[serde(rename = "v")]
@@ -42,13 +96,21 @@ struct MethodParams<'a, P> {
 }
 
 impl Client {
-    pub fn new(token: &str, language: &str, debug: Option<ClientDebug>) -> Self {
+    pub fn new(
+        token: &str,
+        language: &str,
+        rate_limit: ClientRateLimit,
+        retries: ClientRetries,
+        debug: Option<ClientDebug>,
+    ) -> Self {
         Self {
             client: reqwest::Client::builder()
                 .build()
                 .expect("builder should be valid"),
             language: language.to_owned(),
             token: token.to_owned(),
+            limiter: RateLimiter::new(rate_limit.requests_per_second, rate_limit.burst),
+            retries,
             debug,
         }
     }
@@ -57,18 +119,18 @@ impl Client {
     ///
     /// # Параметры
     ///
-    /// * `id` - Короткий адрес пользователя или сообщества.
+    /// * `owner_id` - Идентификатор пользователя или сообщества.
     /// * `offset` - Смещение, необходимое для выборки определённого подмножества записей.
     /// * `count` - Количество записей, которое необходимо получить. Максимальное значение: 100.
     pub async fn get_posts_from_wall(
         &self,
-        id: &VkId,
+        owner_id: VkOwnerId,
         offset: usize,
         count: usize,
     ) -> anyhow::Result<Vec<super::Post>> {
         #[derive(Serialize)]
-        struct Params<'a> {
-            domain: &'a str,
+        struct Params {
+            owner_id: i64,
             offset: usize,
             count: usize,
         }
@@ -76,7 +138,7 @@ impl Client {
         self.get::<_, super::Posts>(
             "wall.get",
             Params {
-                domain: &id.0,
+                owner_id: owner_id.0,
                 offset,
                 count,
             },
@@ -85,7 +147,199 @@ impl Client {
         .map(|posts| posts.items)
     }
 
+    /// Возвращает публикации по их идентификаторам.
+    ///
+    /// Удалённые и недоступные публикации в ответ не попадают.
+    pub async fn get_posts(&self, posts: &[VkPostId]) -> anyhow::Result<Vec<super::Post>> {
+        #[derive(Serialize)]
+        struct Params {
+            posts: String,
+        }
+
+        let posts = posts
+            .iter()
+            .map(|post| format!("{}_{}", post.owner_id.0, post.post_id))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        self.get::<_, Vec<super::Post>>("wall.getById", Params { posts })
+            .await
+    }
+
+    /// Возвращает публикацию по её идентификатору или `None`, если она удалена или недоступна.
+    pub async fn get_post(&self, post: VkPostId) -> anyhow::Result<Option<super::Post>> {
+        self.get_posts(&[post])
+            .await
+            .map(|posts| posts.into_iter().next())
+    }
+
+    /// Возвращает информацию о сообществе.
+    ///
+    /// # Параметры
+    ///
+    /// * `group_id` - Идентификатор сообщества, положительное число.
+    pub async fn get_group_by_id(&self, group_id: i64) -> anyhow::Result<Option<super::Group>> {
+        #[derive(Serialize)]
+        struct Params<'a> {
+            group_id: i64,
+            fields: &'a str,
+        }
+
+        self.get::<_, Vec<super::Group>>(
+            "groups.getById",
+            Params {
+                group_id,
+                fields: "screen_name,photo_200",
+            },
+        )
+        .await
+        .map(|groups| groups.into_iter().next())
+    }
+
+    /// Возвращает информацию о пользователе.
+    ///
+    /// # Параметры
+    ///
+    /// * `user_id` - Идентификатор пользователя.
+    pub async fn get_user(&self, user_id: i64) -> anyhow::Result<Option<super::User>> {
+        #[derive(Serialize)]
+        struct Params<'a> {
+            user_ids: i64,
+            fields: &'a str,
+        }
+
+        self.get::<_, Vec<super::User>>(
+            "users.get",
+            Params {
+                user_ids: user_id,
+                fields: "screen_name,photo_200",
+            },
+        )
+        .await
+        .map(|users| users.into_iter().next())
+    }
+
+    /// Определяет тип объекта и его идентификатор по короткому адресу.
+    ///
+    /// Возвращает `None`, если адрес никому не принадлежит.
+    ///
+    /// # Параметры
+    ///
+    /// * `screen_name` - Короткий адрес пользователя, сообщества или приложения.
+    pub async fn resolve_screen_name(
+        &self,
+        screen_name: &str,
+    ) -> anyhow::Result<Option<super::ResolvedScreenName>> {
+        #[derive(Serialize)]
+        struct Params<'a> {
+            screen_name: &'a str,
+        }
+
+        // Для несуществующего адреса ВК возвращает пустой массив вместо объекта.
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Resolved {
+            Found(super::ResolvedScreenName),
+            NotFound(Vec<serde_json::Value>),
+        }
+
+        let resolved = self
+            .get::<_, Resolved>("utils.resolveScreenName", Params { screen_name })
+            .await?;
+
+        Ok(match resolved {
+            Resolved::Found(resolved) => Some(resolved),
+            Resolved::NotFound(_) => None,
+        })
+    }
+
+    /// Получает публикации с нескольких стен одним вызовом метода `execute`.
+    ///
+    /// Возвращает результаты в том же порядке, что и запросы.
+    /// Ошибка одного запроса не влияет на остальные.
+    ///
+    /// # Параметры
+    ///
+    /// * `requests` - Параметры вызовов `wall.get`. Максимальное количество: 25.
+    pub async fn get_posts_from_walls(
+        &self,
+        requests: &[WallRequest],
+    ) -> anyhow::Result<Vec<anyhow::Result<Vec<super::Post>>>> {
+        #[derive(Serialize)]
+        struct Params {
+            code: String,
+        }
+
+        assert!(
+            requests.len() <= MAX_EXECUTE_CALLS,
+            "execute supports up to {MAX_EXECUTE_CALLS} calls"
+        );
+
+        let calls = requests
+            .iter()
+            .map(|request| {
+                let params = serde_json::json!({
+                    "owner_id": request.owner_id.0,
+                    "offset": request.offset,
+                    "count": request.count,
+                });
+
+                format!("API.wall.get({params})")
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let response = self
+            .request::<_, Vec<serde_json::Value>>(
+                "execute",
+                Params {
+                    code: format!("return [{calls}];"),
+                },
+            )
+            .await?;
+
+        let mut execute_errors = response.execute_errors.into_iter();
+
+        let results = response
+            .response
+            .context("execute response doesn't contain 'response'")?
+            .into_iter()
+            .map(|result| {
+                // Вызовы, завершившиеся ошибкой, возвращают `false`,
+                // а сами ошибки идут по порядку в поле `execute_errors`.
+                if result == serde_json::Value::Bool(false) {
+                    return match execute_errors.next() {
+                        Some(error) => Err(VkError::from(error).into()),
+                        None => Err(anyhow::anyhow!("wall.get failed without error")),
+                    };
+                }
+
+                serde_json::from_value::<super::Posts>(result)
+                    .map(|posts| posts.items)
+                    .context("parsing wall.get response from execute")
+            })
+            .collect::<Vec<_>>();
+
+        if results.len() != requests.len() {
+            anyhow::bail!(
+                "execute returned {got} results for {expected} calls",
+                got = results.len(),
+                expected = requests.len(),
+            );
+        }
+
+        Ok(results)
+    }
+
     async fn get<P, R>(&self, method: &str, params: P) -> anyhow::Result<R>
+    where
+        P: serde::Serialize,
+        R: for<'a> serde::Deserialize<'a>,
+    {
+        self.request(method, params).await?.into_result()
+    }
+
+    async fn request<P, R>(&self, method: &str, params: P) -> anyhow::Result<super::Response<R>>
     where
         P: serde::Serialize,
         R: for<'a> serde::Deserialize<'a>,
@@ -100,12 +354,45 @@ impl Client {
         let url = &format!("{SERVER}/method/{method}?{params}");
         let url = Url::parse(url).expect("url should be valid");
 
+        let max_attempts = self.retries.max_attempts;
+        let mut attempt = 1;
+
+        loop {
+            let error = match self.call(method, url.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(error) => error,
+            };
+
+            if attempt >= max_attempts || !is_transient_error(&error) {
+                return Err(error);
+            }
+
+            let delay = self.retries.delay(attempt);
+
+            log::warn!(
+                "Attempt {attempt}/{max_attempts} to call VK method '{method}' failed, \
+                 retrying in {delay_ms} ms: {error:#}",
+                delay_ms = delay.as_millis(),
+            );
+
+            sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn call<R>(&self, method: &str, url: Url) -> anyhow::Result<super::Response<R>>
+    where
+        R: for<'a> serde::Deserialize<'a>,
+    {
+        self.limiter.acquire().await;
+
         let response = self
             .client
             .get(url)
             .bearer_auth(&self.token)
             .send()
             .await
+            .and_then(|response| response.error_for_status())
             .with_context(|| format!("executing method '{method}'"))?;
 
         let response = response
@@ -115,12 +402,15 @@ impl Client {
 
         self.dump_response(method, &response).await;
 
-        let response = serde_json::from_str::<super::Response<R>>(&response)
+        let mut response = serde_json::from_str::<super::Response<R>>(&response)
             .with_context(|| format!("parsing response '{response}' from method '{method}'"))?;
 
-        // TODO: Обработка ошибок от ВК.
+        if let Some(error) = response.error.take() {
+            return Err(anyhow::Error::new(VkError::from(error)))
+                .with_context(|| format!("calling method '{method}'"));
+        }
 
-        Ok(response.response)
+        Ok(response)
     }
 
     async fn dump_response(&self, method: &str, response: &str) {
@@ -150,3 +440,49 @@ impl Client {
         }
     }
 }
+
+impl ClientRetries {
+    /// Возвращает задержку после неудачной попытки с номером `attempt`:
+    /// экспоненциальную, ограниченную `max_delay`, со случайным разбросом
+    /// до половины значения, чтобы опросчики не повторяли запросы одновременно.
+    fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+
+        let delay = self
+            .initial_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        let jitter = delay.mul_f64(random_fraction() / 2.0);
+
+        delay - jitter
+    }
+}
+
+/// Проверяет, что ошибка временная и вызов метода стоит повторить.
+fn is_transient_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(error) = cause.downcast_ref::<reqwest::Error>() {
+            return error.is_timeout()
+                || error.is_connect()
+                || error.is_request()
+                || error
+                    .status()
+                    .is_some_and(|status| status.is_server_error());
+        }
+
+        matches!(
+            cause.downcast_ref::<VkError>(),
+            Some(VkError::TooManyRequests(_))
+        )
+    })
+}
+
+/// Возвращает псевдослучайное число от 0 до 1.
+///
+/// Для разброса задержек криптостойкость не нужна,
+/// поэтому используется случайный ключ стандартного хэшера.
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,90 @@
+use serde::Deserialize;
+use std::fmt;
+
+/// Объект ошибки, который ВКонтакте возвращает вместо поля `response`.
+///
+/// Описание полей взято из [https://dev.vk.com/ru/reference/errors].
+#[derive(Clone, Debug, Deserialize)]
+pub struct ApiError {
+    /// Код ошибки.
+    pub error_code: i64,
+
+    /// Описание ошибки.
+    pub error_msg: String,
+
+    /// Параметры запроса, который привёл к ошибке.
+    #[serde(default)]
+    pub request_params: Vec<RequestParam>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RequestParam {
+    pub key: String,
+    pub value: String,
+}
+
+/// Ошибка от ВКонтакте, разобранная по типам, которые сервис обрабатывает по-разному.
+#[derive(Clone, Debug)]
+pub enum VkError {
+    /// Превышено количество запросов: коды 6, 9 и 29.
+    TooManyRequests(ApiError),
+
+    /// Неверный или истёкший ключ доступа: код 5.
+    InvalidToken(ApiError),
+
+    /// Доступ запрещён или стена закрыта: коды 15 и 30.
+    AccessDenied(ApiError),
+
+    /// Сообщество или страница заблокированы: код 18.
+    GroupBlocked(ApiError),
+
+    /// Любая другая ошибка.
+    Other(ApiError),
+}
+
+impl VkError {
+    pub fn api_error(&self) -> &ApiError {
+        match self {
+            VkError::TooManyRequests(error)
+            | VkError::InvalidToken(error)
+            | VkError::AccessDenied(error)
+            | VkError::GroupBlocked(error)
+            | VkError::Other(error) => error,
+        }
+    }
+}
+
+impl From<ApiError> for VkError {
+    fn from(error: ApiError) -> Self {
+        match error.error_code {
+            6 | 9 | 29 => VkError::TooManyRequests(error),
+            5 => VkError::InvalidToken(error),
+            15 | 30 => VkError::AccessDenied(error),
+            18 => VkError::GroupBlocked(error),
+            _ => VkError::Other(error),
+        }
+    }
+}
+
+impl fmt::Display for VkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            VkError::TooManyRequests(_) => "too many requests",
+            VkError::InvalidToken(_) => "invalid access token",
+            VkError::AccessDenied(_) => "access denied",
+            VkError::GroupBlocked(_) => "page is blocked or deleted",
+            VkError::Other(_) => "api error",
+        };
+
+        let error = self.api_error();
+
+        write!(
+            f,
+            "{kind} (code {code}): {msg}",
+            code = error.error_code,
+            msg = error.error_msg
+        )
+    }
+}
+
+impl std::error::Error for VkError {}
@@ -0,0 +1,32 @@
+use serde::Deserialize;
+use url::Url;
+
+/// Структура сообщества, взятая из [https://dev.vk.com/ru/reference/objects/group].
+///
+/// Она не содержит все поля. Только необходимые для работы сервиса.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Group {
+    /// Идентификатор сообщества.
+    pub id: i64,
+
+    /// Название сообщества.
+    pub name: String,
+
+    /// Короткий адрес.
+    pub screen_name: Option<String>,
+
+    /// Является ли сообщество закрытым: 0 - открытое, 1 - закрытое, 2 - частное.
+    pub is_closed: u8,
+
+    /// Возвращается, если сообщество удалено или заблокировано: `deleted` или `banned`.
+    pub deactivated: Option<String>,
+
+    /// URL главной фотографии с размером 200x200px.
+    pub photo_200: Option<Url>,
+}
+
+impl Group {
+    pub fn is_open(&self) -> bool {
+        self.is_closed == 0
+    }
+}
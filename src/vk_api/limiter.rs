@@ -0,0 +1,66 @@
+use std::time::Duration;
+use tokio::{sync::Mutex, time::Instant};
+
+/// Ограничитель частоты запросов по алгоритму "ведро с токенами".
+///
+/// Ведро пополняется со скоростью `requests_per_second` токенов в секунду
+/// и вмещает не больше `burst` токенов. Каждый запрос забирает один токен.
+pub struct RateLimiter {
+    bucket: Mutex<Bucket>,
+    requests_per_second: f64,
+    burst: f64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: u32, burst: u32) -> Self {
+        let requests_per_second = f64::from(requests_per_second.max(1));
+        let burst = f64::from(burst.max(1));
+
+        Self {
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                updated_at: Instant::now(),
+            }),
+            requests_per_second,
+            burst,
+        }
+    }
+
+    /// Ожидает, пока можно будет выполнить запрос.
+    ///
+    /// Блокировка `tokio::sync::Mutex` выдаётся в порядке очереди,
+    /// поэтому вызовы, которые не могут выполниться сразу, обслуживаются
+    /// в порядке поступления и ни один опросчик не голодает.
+    pub async fn acquire(&self) {
+        let mut bucket = self.bucket.lock().await;
+
+        self.refill(&mut bucket);
+
+        if bucket.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / self.requests_per_second);
+
+            log::trace!(
+                "VK requests limit reached, waiting {wait_ms} ms",
+                wait_ms = wait.as_millis()
+            );
+
+            tokio::time::sleep(wait).await;
+            self.refill(&mut bucket);
+        }
+
+        bucket.tokens -= 1.0;
+    }
+
+    fn refill(&self, bucket: &mut Bucket) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+
+        bucket.tokens = (bucket.tokens + elapsed * self.requests_per_second).min(self.burst);
+        bucket.updated_at = now;
+    }
+}
@@ -2,9 +2,21 @@
 
 mod attachment;
 mod client;
+mod error;
+mod groups;
+mod limiter;
 mod posts;
 mod response;
+mod screen_name;
+mod users;
 
-pub use client::{Client, ClientDebug};
+pub use client::{
+    Client, ClientDebug, ClientRateLimit, ClientRetries, WallRequest, MAX_EXECUTE_CALLS,
+    MAX_POSTS_BY_ID,
+};
+pub use error::{ApiError, RequestParam, VkError};
+pub use groups::Group;
 pub use posts::*;
 pub use response::Response;
+pub use screen_name::*;
+pub use users::User;
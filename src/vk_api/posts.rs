@@ -17,6 +17,9 @@ pub struct Post {
     /// Идентификатор записи.
     pub id: PostId,
 
+    /// Идентификатор владельца стены, на которой размещена запись.
+    pub owner_id: i64,
+
     /// Идентификатор автора записи (от чьего имени опубликована запись).
     pub from_id: i64,
 
@@ -40,8 +43,13 @@ pub struct Post {
     pub post_type: Type,
 
     /// Массив объектов, соответствующих медиаресурсам, прикреплённым к записи: фотографиям, документам, видеофайлам и другим.
+    #[serde(default)]
     pub attachments: Vec<Attachment>,
 
+    /// Массив, содержащий историю репостов для записи. Первым идёт запись, репост которой сделан.
+    #[serde(default)]
+    pub copy_history: Vec<Post>,
+
     /// Информация о местоположении.
     pub geo: Option<Geolocation>,
 
@@ -109,4 +117,9 @@ impl Post {
     pub fn is_pinned(&self) -> bool {
         self.is_pinned.map(|flag| flag == 1).unwrap_or(false)
     }
+
+    /// Ссылка на запись во ВКонтакте.
+    pub fn url(&self) -> String {
+        format!("https://vk.com/wall{}_{}", self.owner_id, self.id.0)
+    }
 }
@@ -1,7 +1,24 @@
+use super::{ApiError, VkError};
 use serde::Deserialize;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Response<T> {
-    // TODO: Add errors.
-    pub response: T,
+    pub response: Option<T>,
+    pub error: Option<ApiError>,
+
+    /// Ошибки отдельных вызовов внутри метода `execute`.
+    #[serde(default)]
+    pub execute_errors: Vec<ApiError>,
+}
+
+impl<T> Response<T> {
+    /// Возвращает содержимое ответа или ошибку, если ВК её вернул.
+    pub fn into_result(self) -> anyhow::Result<T> {
+        if let Some(error) = self.error {
+            return Err(VkError::from(error).into());
+        }
+
+        self.response
+            .ok_or_else(|| anyhow::anyhow!("response contains neither 'response' nor 'error'"))
+    }
 }
@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+/// Объект, которому принадлежит короткий адрес,
+/// взятый из [https://dev.vk.com/ru/method/utils.resolveScreenName].
+#[derive(Clone, Debug, Deserialize)]
+pub struct ResolvedScreenName {
+    /// Тип объекта.
+    pub r#type: ScreenNameType,
+
+    /// Идентификатор объекта.
+    pub object_id: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenNameType {
+    User,
+    Group,
+    Application,
+
+    #[serde(untagged)]
+    Other(String),
+}
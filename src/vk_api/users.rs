@@ -0,0 +1,42 @@
+use serde::Deserialize;
+use url::Url;
+
+/// Структура пользователя, взятая из [https://dev.vk.com/ru/reference/objects/user].
+///
+/// Она не содержит все поля. Только необходимые для работы сервиса.
+#[derive(Clone, Debug, Deserialize)]
+pub struct User {
+    /// Идентификатор пользователя.
+    pub id: i64,
+
+    /// Имя.
+    pub first_name: String,
+
+    /// Фамилия.
+    pub last_name: String,
+
+    /// Короткий адрес страницы.
+    pub screen_name: Option<String>,
+
+    /// Возвращается, если страница удалена или заблокирована: `deleted` или `banned`.
+    pub deactivated: Option<String>,
+
+    /// Скрыт ли профиль настройками приватности.
+    pub is_closed: Option<bool>,
+
+    /// Может ли текущий ключ видеть профиль при `is_closed = true`.
+    pub can_access_closed: Option<bool>,
+
+    /// URL квадратной фотографии с шириной 200px.
+    pub photo_200: Option<Url>,
+}
+
+impl User {
+    pub fn full_name(&self) -> String {
+        format!("{} {}", self.first_name, self.last_name)
+    }
+
+    pub fn is_accessible(&self) -> bool {
+        !self.is_closed.unwrap_or(false) || self.can_access_closed.unwrap_or(false)
+    }
+}
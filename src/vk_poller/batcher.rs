@@ -0,0 +1,158 @@
+use crate::{domain::VkOwnerId, vk_api};
+use anyhow::anyhow;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::sleep,
+};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+/// Время, в течение которого собираются запросы от каналов,
+/// которым одновременно пора опрашивать свои стены.
+const BATCH_WINDOW: Duration = Duration::from_millis(300);
+
+type WallResult = anyhow::Result<Vec<vk_api::Post>>;
+
+struct PendingRequest {
+    request: vk_api::WallRequest,
+    reply: oneshot::Sender<WallResult>,
+}
+
+/// Объединяет запросы `wall.get` от разных опросчиков в пакеты
+/// и выполняет их одним вызовом `execute`, экономя лимит запросов к ВК.
+#[derive(Clone)]
+pub struct WallBatcher {
+    sender: mpsc::UnboundedSender<PendingRequest>,
+}
+
+impl WallBatcher {
+    pub fn new(
+        vk_client: Arc<vk_api::Client>,
+        tracker: TaskTracker,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        tracker.spawn(run(
+            vk_client,
+            receiver,
+            tracker.clone(),
+            cancellation_token,
+        ));
+
+        Self { sender }
+    }
+
+    /// Возвращает список публикаций со стены пользователя или сообщества.
+    ///
+    /// Запрос попадает в ближайший пакет и выполняется вместе
+    /// с запросами других каналов.
+    pub async fn get_posts_from_wall(
+        &self,
+        owner_id: VkOwnerId,
+        offset: usize,
+        count: usize,
+    ) -> WallResult {
+        let (reply, result) = oneshot::channel();
+
+        let request = vk_api::WallRequest {
+            owner_id,
+            offset,
+            count,
+        };
+
+        self.sender
+            .send(PendingRequest { request, reply })
+            .map_err(|_| anyhow!("wall batcher is stopped"))?;
+
+        result
+            .await
+            .map_err(|_| anyhow!("wall batcher dropped request"))?
+    }
+}
+
+async fn run(
+    vk_client: Arc<vk_api::Client>,
+    mut receiver: mpsc::UnboundedReceiver<PendingRequest>,
+    tracker: TaskTracker,
+    cancellation_token: CancellationToken,
+) {
+    loop {
+        let first = tokio::select! {
+            _ = cancellation_token.cancelled() => { break; },
+            request = receiver.recv() => match request {
+                Some(request) => request,
+                None => break,
+            },
+        };
+
+        let mut batch = vec![first];
+
+        let window = sleep(BATCH_WINDOW);
+        tokio::pin!(window);
+
+        while batch.len() < vk_api::MAX_EXECUTE_CALLS {
+            tokio::select! {
+                _ = &mut window => { break; },
+                request = receiver.recv() => match request {
+                    Some(request) => batch.push(request),
+                    None => break,
+                },
+            }
+        }
+
+        // Пакет выполняется в отдельной задаче, чтобы пока он ждёт
+        // ограничителя запросов, собирался следующий.
+        tracker.spawn(execute_batch(vk_client.clone(), batch));
+    }
+}
+
+async fn execute_batch(vk_client: Arc<vk_api::Client>, mut batch: Vec<PendingRequest>) {
+    log::debug!(
+        "Fetching {count} VK walls in one batch",
+        count = batch.len()
+    );
+
+    // Одиночный запрос нет смысла оборачивать в `execute`.
+    if batch.len() == 1 {
+        let pending = batch.remove(0);
+        let request = &pending.request;
+
+        let result = vk_client
+            .get_posts_from_wall(request.owner_id, request.offset, request.count)
+            .await;
+
+        _ = pending.reply.send(result);
+
+        return;
+    }
+
+    let requests = batch
+        .iter()
+        .map(|pending| pending.request.clone())
+        .collect::<Vec<_>>();
+
+    match vk_client.get_posts_from_walls(&requests).await {
+        Ok(results) => {
+            for (pending, result) in batch.into_iter().zip(results) {
+                _ = pending.reply.send(result);
+            }
+        }
+        Err(err) => {
+            for pending in batch {
+                _ = pending.reply.send(Err(share_error(&err)));
+            }
+        }
+    }
+}
+
+/// Копирует ошибку пакетного запроса для каждого опросчика,
+/// сохраняя типизированную ошибку ВК, если она есть.
+fn share_error(err: &anyhow::Error) -> anyhow::Error {
+    match err.downcast_ref::<vk_api::VkError>() {
+        Some(vk_error) => {
+            anyhow::Error::new(vk_error.clone()).context("executing batched wall.get")
+        }
+        None => anyhow!("executing batched wall.get: {err:#}"),
+    }
+}
@@ -1,80 +1,749 @@
+use super::markup::{self, Node};
 use anyhow::{anyhow, Context};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::borrow::Cow;
+use std::collections::HashMap;
+use teloxide::types::MessageEntityKind;
+use url::Url;
 
 use crate::{
-    domain::{TelegramChannelId, TelegramPost, TelegramPostPhoto},
+    domain::{
+        PostLayout, TelegramChannelId, TelegramMediaKind, TelegramPoll, TelegramPost,
+        TelegramPostMedia, TelegramText,
+    },
     vk_api,
 };
 
+/// Максимальный размер файла, который бот может загрузить в Telegram.
+const TELEGRAM_UPLOAD_LIMIT: u64 = 50 * 1024 * 1024;
+
+/// Максимальное количество вариантов ответа в опросе Telegram.
+const TELEGRAM_POLL_OPTIONS_LIMIT: usize = 10;
+
+/// Максимальная длина вопроса опроса Telegram в символах.
+const TELEGRAM_POLL_QUESTION_LIMIT: usize = 300;
+
+/// Максимальная длина варианта ответа опроса Telegram в символах.
+const TELEGRAM_POLL_OPTION_LIMIT: usize = 100;
+
+/// Названия стен, с которых сделаны репосты, по идентификатору владельца.
+pub type SourceNames = HashMap<i64, String>;
+
+/// Текст и вложения публикации, собранные вместе с её репостами.
+#[derive(Default)]
+struct PostContent {
+    /// Абзацы текста.
+    blocks: Vec<TelegramText>,
+    media: Vec<TelegramPostMedia>,
+    poll: Option<TelegramPoll>,
+    link_preview: bool,
+}
+
 pub async fn vk_to_tg(
     channel_id: TelegramChannelId,
-    post: vk_api::Post,
+    mut post: vk_api::Post,
+    sources: &SourceNames,
+    layout: PostLayout,
 ) -> anyhow::Result<TelegramPost> {
-    let mut photos = Vec::new();
+    let is_pinned = post.is_pinned == Some(1);
+
+    let mut reposts = Vec::new();
+    flatten_reposts(std::mem::take(&mut post.copy_history), &mut reposts);
+
+    let mut content = PostContent::default();
+
+    // Комментарий к репосту идёт первым, за ним исходные записи.
+    convert_content(post, &mut content).await?;
+
+    for repost in reposts {
+        content.blocks.push(repost_header(&repost, sources));
+        convert_content(repost, &mut content).await?;
+    }
+
+    Ok(TelegramPost {
+        channel_id,
+        text: TelegramText::join(content.blocks, "\n\n"),
+        media: content.media,
+        poll: content.poll,
+        is_pinned,
+        link_preview: content.link_preview,
+        layout,
+    })
+}
+
+/// Возвращает владельцев стен, с которых сделаны репосты, включая вложенные.
+pub fn repost_owner_ids(post: &vk_api::Post) -> Vec<i64> {
+    let mut owner_ids = Vec::new();
+
+    for repost in &post.copy_history {
+        owner_ids.push(repost.owner_id);
+        owner_ids.extend(repost_owner_ids(repost));
+    }
+
+    owner_ids
+}
+
+/// Раскладывает историю репостов в плоский список в порядке вложенности.
+fn flatten_reposts(copy_history: Vec<vk_api::Post>, reposts: &mut Vec<vk_api::Post>) {
+    for mut repost in copy_history {
+        let nested = std::mem::take(&mut repost.copy_history);
+        reposts.push(repost);
+        flatten_reposts(nested, reposts);
+    }
+}
+
+/// Заголовок репоста со ссылкой на исходную запись.
+fn repost_header(repost: &vk_api::Post, sources: &SourceNames) -> TelegramText {
+    let url = repost.url();
+
+    let mut header = TelegramText::plain("🔁 ");
+
+    match sources.get(&repost.owner_id) {
+        Some(name) => {
+            header.push("Репост из ");
+            push_link(&mut header, name, &url);
+        }
+        None => push_link(&mut header, "Репост", &url),
+    }
+
+    header
+}
+
+async fn convert_content(post: vk_api::Post, content: &mut PostContent) -> anyhow::Result<()> {
+    // Вложения, которые нельзя загрузить файлом, добавляются строками к тексту.
+    let mut extra_lines = Vec::new();
+
+    // Аудиозаписи нельзя скачать с сервисным ключом, поэтому они собираются в список треков.
+    let mut tracks = Vec::new();
+
+    let post_url = post.url();
 
     for attachment in post.attachments {
         match attachment {
-            vk_api::Attachment::Photo(photo) => {
-                let description = photo.description;
+            vk_api::Attachment::Photo(photo) => content.media.push(convert_photo(photo).await?),
+            vk_api::Attachment::Video(video) => match convert_video(&video).await? {
+                Some(video) => content.media.push(video),
+                None => extra_lines.push(text_with_urls(&video_caption(&video))),
+            },
+            vk_api::Attachment::Doc(doc) => match convert_doc(&doc).await? {
+                Some(doc) => content.media.push(doc),
+                None => extra_lines.push(text_with_urls(&doc_caption(&doc))),
+            },
+            vk_api::Attachment::Graffiti(graffiti) => {
+                let id = format!("graffiti{}_{}", graffiti.owner_id, graffiti.id);
+                content
+                    .media
+                    .push(convert_graffiti(id, graffiti.url).await?);
+            }
+            vk_api::Attachment::Audio(audio) => tracks.push(audio_caption(&audio)),
+            vk_api::Attachment::Podcast(podcast) => {
+                extra_lines.push(text_with_urls(&podcast_caption(&podcast)))
+            }
+            vk_api::Attachment::Poll(_) if content.poll.is_some() => {
+                log::warn!("Skipping second VK poll in post #{}", post.id.0);
+            }
+            vk_api::Attachment::Poll(vk_poll) => {
+                let answers_count = vk_poll.answers.len();
 
-                let photo_url = photo
-                    .sizes
-                    .into_iter()
-                    .find(|size| size.r#type == vk_api::PhotoType::W)
-                    .map(|size| size.url)
-                    .ok_or_else(|| anyhow!("failed to find W type photo size"))?;
+                let Some(poll) = convert_poll(vk_poll) else {
+                    extra_lines.push(text_with_urls(&format!(
+                        "📊 Этот опрос нельзя перенести в Telegram, проголосовать можно во ВКонтакте:\n{post_url}"
+                    )));
+                    continue;
+                };
 
-                let bytes = reqwest::get(photo_url)
-                    .await
-                    .context("requesting photo from VK")?
-                    .bytes()
-                    .await
-                    .context("downloading photo from VK")?
-                    .to_vec();
+                if answers_count > TELEGRAM_POLL_OPTIONS_LIMIT {
+                    extra_lines.push(text_with_urls(&format!(
+                        "📊 В опросе показаны первые {TELEGRAM_POLL_OPTIONS_LIMIT} вариантов ответа из {answers_count}, остальные — во ВКонтакте:\n{post_url}",
+                    )));
+                }
 
-                photos.push(TelegramPostPhoto { bytes, description })
+                content.poll = Some(poll);
+            }
+            vk_api::Attachment::Link(link) => {
+                extra_lines.push(link_caption(&link));
+
+                // Обложка ссылки отправляется фотографией, иначе превью построит Telegram.
+                match link.photo {
+                    Some(photo) => content.media.push(convert_photo(photo).await?),
+                    None => content.link_preview = true,
+                }
+            }
+            vk_api::Attachment::PhotosList(_)
+            | vk_api::Attachment::Album(_)
+            | vk_api::Attachment::Event(_) => {
+                log::warn!("Skipping unsupported VK attachment in post #{}", post.id.0);
             }
-            _ => {
-                unimplemented!()
+            vk_api::Attachment::Unsupported(kind) => {
+                log::warn!(
+                    "Skipping unsupported VK attachment '{kind}' in post #{}",
+                    post.id.0
+                );
             }
         }
     }
 
-    Ok(TelegramPost {
-        channel_id,
-        text: vk_format_to_markdown(&post.text),
-        photos,
-        is_pinned: post.is_pinned == Some(1),
+    if !tracks.is_empty() {
+        extra_lines.insert(0, TelegramText::plain(tracks.join("\n")));
+    }
+
+    let text = vk_markup_to_text(&post.text);
+
+    if !text.is_empty() {
+        content.blocks.push(text);
+    }
+
+    content.blocks.extend(extra_lines);
+
+    Ok(())
+}
+
+async fn convert_photo(photo: vk_api::Photo) -> anyhow::Result<TelegramPostMedia> {
+    let id = format!("photo{}_{}", photo.owner_id, photo.id);
+
+    let photo_url = photo
+        .largest_size()
+        .map(|size| size.url.clone())
+        .ok_or_else(|| anyhow!("failed to find photo size"))?;
+
+    let bytes = reqwest::get(photo_url)
+        .await
+        .context("requesting photo from VK")?
+        .bytes()
+        .await
+        .context("downloading photo from VK")?
+        .to_vec();
+
+    Ok(TelegramPostMedia {
+        id,
+        kind: TelegramMediaKind::Photo,
+        bytes,
+        file_name: None,
+        description: TelegramText::plain(photo.description),
+    })
+}
+
+/// Загружает видеозапись файлом, если ВКонтакте отдал ссылку на него и он укладывается
+/// в ограничения Telegram. Иначе вместо видео отправляется обложка со ссылкой.
+///
+/// Возвращает `None`, если у видеозаписи нет ни файла, ни обложки.
+async fn convert_video(video: &vk_api::Video) -> anyhow::Result<Option<TelegramPostMedia>> {
+    let id = format!("video{}_{}", video.owner_id, video.id);
+
+    for url in video.file_urls() {
+        match download(url.clone(), TELEGRAM_UPLOAD_LIMIT).await {
+            Ok(Some(bytes)) => {
+                return Ok(Some(TelegramPostMedia {
+                    file_name: Some(format!("{id}.mp4")),
+                    id,
+                    kind: TelegramMediaKind::Video,
+                    bytes,
+                    description: text_with_urls(&video_caption(video)),
+                }));
+            }
+            Ok(None) => continue,
+            Err(err) => {
+                log::warn!("Failed to download VK video {id}, trying another quality: {err:#}");
+            }
+        }
+    }
+
+    let Some(image) = video.largest_image() else {
+        return Ok(None);
+    };
+
+    let bytes = download(image.url.clone(), TELEGRAM_UPLOAD_LIMIT)
+        .await
+        .context("downloading video thumbnail from VK")?
+        .ok_or_else(|| anyhow!("video thumbnail is too large"))?;
+
+    Ok(Some(TelegramPostMedia {
+        id,
+        kind: TelegramMediaKind::Photo,
+        bytes,
+        file_name: None,
+        description: text_with_urls(&video_caption(video)),
+    }))
+}
+
+/// Загружает документ файлом: GIF как анимацию, граффити как фотографию,
+/// остальное как документ с исходным именем файла.
+///
+/// Возвращает `None`, если документ недоступен или больше ограничения Telegram,
+/// тогда вместо него отправляется ссылка.
+async fn convert_doc(doc: &vk_api::Doc) -> anyhow::Result<Option<TelegramPostMedia>> {
+    let id = format!("doc{}_{}", doc.owner_id, doc.id);
+
+    if let Some(graffiti) = doc
+        .preview
+        .as_ref()
+        .and_then(|preview| preview.graffiti.as_ref())
+    {
+        return convert_graffiti(id, graffiti.src.clone()).await.map(Some);
+    }
+
+    let Some(url) = &doc.url else {
+        return Ok(None);
+    };
+
+    if doc.size > TELEGRAM_UPLOAD_LIMIT {
+        return Ok(None);
+    }
+
+    let Some(bytes) = download(url.clone(), TELEGRAM_UPLOAD_LIMIT)
+        .await
+        .context("downloading document from VK")?
+    else {
+        return Ok(None);
+    };
+
+    let kind = match doc.r#type {
+        vk_api::DocType::Gif => TelegramMediaKind::Animation,
+        _ => TelegramMediaKind::Document,
+    };
+
+    Ok(Some(TelegramPostMedia {
+        id,
+        kind,
+        bytes,
+        file_name: Some(doc.file_name()),
+        description: TelegramText::default(),
+    }))
+}
+
+async fn convert_graffiti(id: String, url: Url) -> anyhow::Result<TelegramPostMedia> {
+    let bytes = download(url, TELEGRAM_UPLOAD_LIMIT)
+        .await
+        .context("downloading graffiti from VK")?
+        .ok_or_else(|| anyhow!("graffiti is too large"))?;
+
+    Ok(TelegramPostMedia {
+        id,
+        kind: TelegramMediaKind::Photo,
+        bytes,
+        file_name: None,
+        description: TelegramText::default(),
+    })
+}
+
+/// Подпись к документу, который не получилось загрузить: название, размер и ссылка.
+fn doc_caption(doc: &vk_api::Doc) -> String {
+    format!(
+        "📎 {name} ({size})\n{url}",
+        name = doc.file_name(),
+        size = format_size(doc.size),
+        url = doc.page_url(),
+    )
+}
+
+/// Форматирует размер файла в байтах.
+fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = 1024 * KB;
+
+    match bytes {
+        bytes if bytes >= MB => format!("{:.1} МБ", bytes as f64 / MB as f64),
+        bytes if bytes >= KB => format!("{} КБ", bytes / KB),
+        bytes => format!("{bytes} Б"),
+    }
+}
+
+/// Подпись к видеозаписи: название, длительность и ссылка на неё во ВКонтакте.
+fn video_caption(video: &vk_api::Video) -> String {
+    let title = match video.title.trim() {
+        "" => "Видео",
+        title => title,
+    };
+
+    match video.duration {
+        Some(duration) if duration > 0 => {
+            format!(
+                "🎬 {title} ({})\n{}",
+                format_duration(duration),
+                video.url()
+            )
+        }
+        _ => format!("🎬 {title}\n{}", video.url()),
+    }
+}
+
+/// Переносит опрос в Telegram, обрезая его под ограничения Telegram.
+///
+/// Возвращает `None`, если Telegram не примет такой опрос: без вопроса
+/// или меньше чем с двумя вариантами ответа.
+fn convert_poll(poll: vk_api::Poll) -> Option<TelegramPoll> {
+    let question = truncate(&poll.question, TELEGRAM_POLL_QUESTION_LIMIT);
+
+    let options = poll
+        .answers
+        .iter()
+        .map(|answer| truncate(&answer.text, TELEGRAM_POLL_OPTION_LIMIT))
+        .filter(|option| !option.is_empty())
+        .take(TELEGRAM_POLL_OPTIONS_LIMIT)
+        .collect::<Vec<_>>();
+
+    if question.is_empty() || options.len() < 2 {
+        return None;
+    }
+
+    let close_datetime = poll.end_datetime();
+    let is_closed = poll.closed || close_datetime.is_some_and(|dt| dt <= chrono::Utc::now());
+
+    Some(TelegramPoll {
+        id: format!("poll{}_{}", poll.owner_id, poll.id),
+        question,
+        options,
+        is_anonymous: poll.anonymous,
+        allows_multiple_answers: poll.multiple,
+        is_closed,
+        close_datetime,
     })
 }
 
-fn vk_format_to_markdown(text: &str) -> String {
-    let text = convert_links(text);
-    escape_characters(&text)
+/// Обрезает строку до `limit` символов, заменяя конец многоточием.
+fn truncate(text: &str, limit: usize) -> String {
+    let text = text.trim();
+
+    if text.chars().count() <= limit {
+        return text.to_owned();
+    }
+
+    let mut text = text.chars().take(limit - 1).collect::<String>();
+    text.push('…');
+    text
+}
+
+/// Строки со ссылкой: заголовок и под ним настоящий адрес, на который она ведёт.
+fn link_caption(link: &vk_api::Link) -> TelegramText {
+    let url = markup::unwrap_away_link(&link.url);
+
+    match link.title.trim() {
+        "" => text_with_urls(&format!("🔗 {url}")),
+        title => text_with_urls(&format!("🔗 {title}\n{url}")),
+    }
+}
+
+/// Строка списка треков: исполнитель, название и длительность.
+fn audio_caption(audio: &vk_api::Audio) -> String {
+    format!(
+        "🎵 {} — {} ({})",
+        audio.artist.trim(),
+        audio.title.trim(),
+        format_duration(audio.duration)
+    )
+}
+
+/// Подпись к эпизоду подкаста: подкаст, название эпизода, длительность и ссылка.
+fn podcast_caption(podcast: &vk_api::Podcast) -> String {
+    format!(
+        "🎙 {} — {} ({})\n{}",
+        podcast.artist.trim(),
+        podcast.title.trim(),
+        format_duration(podcast.duration),
+        podcast.url()
+    )
+}
+
+/// Форматирует длительность в секундах как `м:сс` или `ч:мм:сс`.
+fn format_duration(secs: i64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+/// Скачивает файл, если его размер не превышает `limit` байт.
+///
+/// Возвращает `None`, если файл больше ограничения.
+async fn download(url: Url, limit: u64) -> anyhow::Result<Option<Vec<u8>>> {
+    let response = reqwest::get(url)
+        .await
+        .context("requesting file")?
+        .error_for_status()
+        .context("requesting file")?;
+
+    if response
+        .content_length()
+        .is_some_and(|length| length > limit)
+    {
+        return Ok(None);
+    }
+
+    let bytes = response.bytes().await.context("downloading file")?;
+
+    if bytes.len() as u64 > limit {
+        return Ok(None);
+    }
+
+    Ok(Some(bytes.to_vec()))
+}
+
+/// Вычисляет хэш текста и вложений публикации, по которому обнаруживаются её правки.
+///
+/// Хэш сохраняется в базе данных, поэтому он не должен зависеть от версии компилятора
+/// и порядка запуска. Используется FNV-1a.
+pub fn content_hash(post: &vk_api::Post) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let mut content = String::new();
+    push_hashed_content(post, &mut content);
+
+    let hash = content.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    });
+
+    format!("{hash:016x}")
+}
+
+fn push_hashed_content(post: &vk_api::Post, content: &mut String) {
+    content.push_str(&post.text);
+
+    for attachment in &post.attachments {
+        content.push('\0');
+
+        match attachment {
+            vk_api::Attachment::Photo(photo) => {
+                content.push_str(&format!(
+                    "photo{}_{}:{}",
+                    photo.owner_id, photo.id, photo.description
+                ));
+            }
+            vk_api::Attachment::Video(video) => {
+                content.push_str(&format!(
+                    "video{}_{}:{}",
+                    video.owner_id, video.id, video.title
+                ));
+            }
+            vk_api::Attachment::Doc(doc) => {
+                content.push_str(&format!("doc{}_{}:{}", doc.owner_id, doc.id, doc.title));
+            }
+            vk_api::Attachment::Graffiti(graffiti) => {
+                content.push_str(&format!("graffiti{}_{}", graffiti.owner_id, graffiti.id));
+            }
+            vk_api::Attachment::Audio(audio) => {
+                content.push_str(&format!("audio{}_{}", audio.owner_id, audio.id));
+            }
+            vk_api::Attachment::Podcast(podcast) => {
+                content.push_str(&format!("podcast{}_{}", podcast.owner_id, podcast.id));
+            }
+            vk_api::Attachment::Poll(poll) => {
+                content.push_str(&format!("poll{}_{}", poll.owner_id, poll.id));
+            }
+            vk_api::Attachment::Link(link) => {
+                content.push_str(&format!("link:{}:{}", link.url, link.title));
+            }
+            _ => content.push('?'),
+        }
+    }
+
+    for repost in &post.copy_history {
+        content.push('\u{1}');
+        push_hashed_content(repost, content);
+    }
+}
+
+/// Переводит разметку ВКонтакте в текст с сущностями Telegram.
+fn vk_markup_to_text(text: &str) -> TelegramText {
+    let mut converted = TelegramText::default();
+
+    for node in markup::parse(text) {
+        match node {
+            Node::Text(text) => converted.append(text_with_urls(&text)),
+            Node::Mention { target, text } => {
+                push_link(&mut converted, &text, &format!("https://vk.com/{target}"))
+            }
+            Node::Link { url, text } => push_link(&mut converted, &text, &url),
+            // Telegram не знает хэштегов сообществ ВКонтакте, поэтому остаётся только тег.
+            Node::Hashtag { tag, domain: _ } => {
+                converted.push_entity(&format!("#{tag}"), MessageEntityKind::Hashtag)
+            }
+        }
+    }
+
+    converted
+}
+
+/// Добавляет текст со ссылкой. Если адрес некорректен, текст добавляется без неё.
+fn push_link(text: &mut TelegramText, label: &str, url: &str) {
+    match Url::parse(url) {
+        Ok(url) => text.push_entity(label, MessageEntityKind::TextLink { url }),
+        Err(_) => text.push(label),
+    }
 }
 
-fn convert_links(text: &str) -> Cow<str> {
-    static LINKS_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[(.+)\|(.+)\]").unwrap());
+/// Размечает адреса в обычном тексте.
+fn text_with_urls(text: &str) -> TelegramText {
+    static URL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://\S+").unwrap());
+
+    // Знаки препинания в конце адреса обычно завершают предложение.
+    const TRAILING_PUNCTUATION: &[char] = &['.', ',', ':', ';', '!', '?', ')', '»', '"', '\''];
 
-    LINKS_REGEX.replace_all(text, "[$2](https://vk.com/$1)")
+    let mut converted = TelegramText::default();
+    let mut plain_start = 0;
+
+    for found in URL_REGEX.find_iter(text) {
+        let url = found.as_str().trim_end_matches(TRAILING_PUNCTUATION);
+
+        converted.push(&text[plain_start..found.start()]);
+        converted.push_entity(url, MessageEntityKind::Url);
+
+        plain_start = found.start() + url.len();
+    }
+
+    converted.push(&text[plain_start..]);
+    converted
 }
 
-fn escape_characters(text: &str) -> String {
-    const SPECIAL_SYMBOLS: [char; 16] = [
-        '+', '-', '=', '#', '!', '?', '_', '.', '*', '[', ']', '(', ')', '{', '}', '`',
-    ];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use teloxide::types::MessageEntity;
+
+    fn entity(kind: MessageEntityKind, offset: usize, length: usize) -> MessageEntity {
+        MessageEntity {
+            kind,
+            offset,
+            length,
+        }
+    }
+
+    fn text_link(url: &str) -> MessageEntityKind {
+        MessageEntityKind::TextLink {
+            url: Url::parse(url).unwrap(),
+        }
+    }
+
+    #[test]
+    fn renders_mentions_as_links() {
+        let text = vk_markup_to_text("Привет, [id1|Павел] и @club2 (клуб)!");
 
-    let mut escaped = String::with_capacity(text.len());
+        assert_eq!(text.text, "Привет, Павел и клуб!");
+        assert_eq!(
+            text.entities,
+            [
+                entity(text_link("https://vk.com/id1"), 8, 5),
+                entity(text_link("https://vk.com/club2"), 16, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_external_links() {
+        let text = vk_markup_to_text(
+            "[https://vk.com/away.php?to=https%3A%2F%2Fexample.com%2Fpage|статья] и [vk.com/wall-1_2|пост]",
+        );
+
+        assert_eq!(text.text, "статья и пост");
+        assert_eq!(
+            text.entities,
+            [
+                entity(text_link("https://example.com/page"), 0, 6),
+                entity(text_link("https://vk.com/wall-1_2"), 9, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_hashtags_without_domain() {
+        let text = vk_markup_to_text("#новости@public и #tag");
+
+        assert_eq!(text.text, "#новости и #tag");
+        assert_eq!(
+            text.entities,
+            [
+                entity(MessageEntityKind::Hashtag, 0, 8),
+                entity(MessageEntityKind::Hashtag, 11, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_plain_urls_without_trailing_punctuation() {
+        let text = vk_markup_to_text("См. https://example.com/a.");
+
+        assert_eq!(text.text, "См. https://example.com/a.");
+        assert_eq!(text.entities, [entity(MessageEntityKind::Url, 4, 21)]);
+    }
+
+    #[test]
+    fn counts_offsets_in_utf16() {
+        let text = vk_markup_to_text("😀 [id1|Павел] #tag");
+
+        assert_eq!(text.text, "😀 Павел #tag");
+        assert_eq!(
+            text.entities,
+            [
+                entity(text_link("https://vk.com/id1"), 3, 5),
+                entity(MessageEntityKind::Hashtag, 9, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_unparsed_markup_as_text() {
+        let text = vk_markup_to_text("[id1|Павел #123");
+
+        assert_eq!(text.text, "[id1|Павел #123");
+        assert!(text.entities.is_empty());
+    }
 
-    for chr in text.chars() {
-        if SPECIAL_SYMBOLS.contains(&chr) {
-            escaped.push('\\');
+    fn vk_poll(question: &str, answers: &[&str]) -> vk_api::Poll {
+        vk_api::Poll {
+            id: 1,
+            owner_id: -1,
+            question: question.to_owned(),
+            answers: answers
+                .iter()
+                .zip(1..)
+                .map(|(text, id)| vk_api::PollAnswer {
+                    id,
+                    text: (*text).to_owned(),
+                })
+                .collect(),
+            multiple: false,
+            anonymous: true,
+            end_date: 0,
+            closed: false,
         }
+    }
+
+    #[test]
+    fn converts_poll() {
+        let answers = (1..=12).map(|n| n.to_string()).collect::<Vec<_>>();
+        let answers = answers.iter().map(String::as_str).collect::<Vec<_>>();
 
-        escaped.push(chr);
+        let poll = convert_poll(vk_poll(" Вопрос? ", &answers)).unwrap();
+
+        assert_eq!(poll.id, "poll-1_1");
+        assert_eq!(poll.question, "Вопрос?");
+        assert_eq!(poll.options.len(), TELEGRAM_POLL_OPTIONS_LIMIT);
+        assert!(!poll.is_closed);
+    }
+
+    #[test]
+    fn skips_polls_rejected_by_telegram() {
+        assert!(convert_poll(vk_poll("  ", &["Да", "Нет"])).is_none());
+        assert!(convert_poll(vk_poll("Вопрос?", &["Да"])).is_none());
+        assert!(convert_poll(vk_poll("Вопрос?", &["Да", " "])).is_none());
     }
 
-    escaped
+    #[test]
+    fn renders_link_title_and_unwrapped_url() {
+        let link = vk_api::Link {
+            url: "https://vk.com/away.php?to=https%3A%2F%2Fexample.com%2Fpage".to_owned(),
+            title: "Статья".to_owned(),
+            caption: None,
+            description: None,
+            photo: None,
+        };
+
+        let caption = link_caption(&link);
+
+        assert_eq!(caption.text, "🔗 Статья\nhttps://example.com/page");
+        assert_eq!(caption.entities, [entity(MessageEntityKind::Url, 10, 24)]);
+    }
 }
@@ -0,0 +1,338 @@
+//! Разбор разметки текста публикаций ВКонтакте.
+//!
+//! Текст записи на стене может содержать упоминания `[id1|Имя]` и `@id1 (Имя)`,
+//! внешние ссылки `[https://example.com|текст]` и хэштеги `#тег` или `#тег@паблик`.
+//! Всё остальное считается обычным текстом.
+
+use url::Url;
+
+/// Фрагмент текста публикации.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    /// Обычный текст без разметки.
+    Text(String),
+
+    /// Упоминание пользователя или сообщества.
+    Mention {
+        /// Идентификатор или короткий адрес, например `id1` или `club1`.
+        target: String,
+        text: String,
+    },
+
+    /// Ссылка с текстом.
+    Link { url: String, text: String },
+
+    /// Хэштег, возможно, привязанный к сообществу: `#тег@паблик`.
+    Hashtag { tag: String, domain: Option<String> },
+}
+
+/// Разбивает текст публикации на фрагменты.
+///
+/// Разметка, которую не удалось разобрать, остаётся обычным текстом.
+pub fn parse(text: &str) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    let mut plain_start = 0;
+    let mut pos = 0;
+
+    while let Some(chr) = text[pos..].chars().next() {
+        let rest = &text[pos..];
+
+        let token = match chr {
+            '[' => parse_bracket(rest),
+            '@' | '*' if is_boundary(text, pos) => parse_inline_mention(rest),
+            '#' if is_boundary(text, pos) => parse_hashtag(rest),
+            _ => None,
+        };
+
+        match token {
+            Some((node, len)) => {
+                push_text(&mut nodes, &text[plain_start..pos]);
+                nodes.push(node);
+
+                pos += len;
+                plain_start = pos;
+            }
+            None => pos += chr.len_utf8(),
+        }
+    }
+
+    push_text(&mut nodes, &text[plain_start..]);
+
+    nodes
+}
+
+/// Возвращает настоящий адрес ссылки, если она ведёт через `vk.com/away.php`.
+pub fn unwrap_away_link(url: &str) -> String {
+    let Ok(parsed) = Url::parse(url) else {
+        return url.to_owned();
+    };
+
+    let is_vk = matches!(parsed.host_str(), Some("vk.com" | "m.vk.com"));
+
+    if !is_vk || parsed.path() != "/away.php" {
+        return url.to_owned();
+    }
+
+    parsed
+        .query_pairs()
+        .find(|(key, _)| key == "to")
+        .map(|(_, to)| to.into_owned())
+        .unwrap_or_else(|| url.to_owned())
+}
+
+/// Разбирает `[цель|текст]`, где цель — упоминание или ссылка.
+fn parse_bracket(rest: &str) -> Option<(Node, usize)> {
+    let end = rest.find(']')?;
+    let inner = &rest[1..end];
+
+    if inner.contains(['[', '\n']) {
+        return None;
+    }
+
+    let (target, text) = inner.split_once('|')?;
+
+    if target.is_empty() || text.is_empty() {
+        return None;
+    }
+
+    let node = if let Some(url) = link_target(target) {
+        Node::Link {
+            url,
+            text: text.to_owned(),
+        }
+    } else if is_screen_name(target) {
+        Node::Mention {
+            target: target.to_owned(),
+            text: text.to_owned(),
+        }
+    } else {
+        return None;
+    };
+
+    Some((node, end + 1))
+}
+
+/// Разбирает `@цель (текст)` или `*цель (текст)`.
+fn parse_inline_mention(rest: &str) -> Option<(Node, usize)> {
+    let target_len = screen_name_len(&rest[1..]);
+
+    if target_len == 0 {
+        return None;
+    }
+
+    let target = &rest[1..1 + target_len];
+    let after_target = &rest[1 + target_len..];
+
+    let text_start = after_target.strip_prefix(" (")?;
+    let text_end = text_start.find(')')?;
+    let text = &text_start[..text_end];
+
+    if text.is_empty() || text.contains(['(', '\n']) {
+        return None;
+    }
+
+    let len = 1 + target_len + " (".len() + text_end + ")".len();
+
+    Some((
+        Node::Mention {
+            target: target.to_owned(),
+            text: text.to_owned(),
+        },
+        len,
+    ))
+}
+
+/// Разбирает `#тег` или `#тег@паблик`.
+fn parse_hashtag(rest: &str) -> Option<(Node, usize)> {
+    let tag_len = rest[1..]
+        .char_indices()
+        .find(|(_, chr)| !is_word_char(*chr))
+        .map(|(idx, _)| idx)
+        .unwrap_or(rest.len() - 1);
+
+    let tag = &rest[1..1 + tag_len];
+
+    // ВКонтакте не считает хэштегом `#1` и подобные.
+    if tag.chars().all(|chr| chr.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut len = 1 + tag_len;
+    let mut domain = None;
+
+    if let Some(after_tag) = rest[len..].strip_prefix('@') {
+        let domain_len = screen_name_len(after_tag);
+
+        if domain_len > 0 {
+            domain = Some(after_tag[..domain_len].to_owned());
+            len += 1 + domain_len;
+        }
+    }
+
+    Some((
+        Node::Hashtag {
+            tag: tag.to_owned(),
+            domain,
+        },
+        len,
+    ))
+}
+
+/// Возвращает адрес внешней ссылки или `None`, если цель не похожа на ссылку.
+fn link_target(target: &str) -> Option<String> {
+    if target.starts_with("http://") || target.starts_with("https://") {
+        Some(unwrap_away_link(target))
+    } else if target.starts_with("vk.com/") || target.starts_with("m.vk.com/") {
+        Some(unwrap_away_link(&format!("https://{target}")))
+    } else {
+        None
+    }
+}
+
+fn is_screen_name(text: &str) -> bool {
+    !text.is_empty() && screen_name_len(text) == text.len()
+}
+
+/// Длина короткого адреса в начале строки. Точка в конце считается концом предложения.
+fn screen_name_len(text: &str) -> usize {
+    let len = text
+        .find(|chr: char| !(chr.is_ascii_alphanumeric() || chr == '_' || chr == '.'))
+        .unwrap_or(text.len());
+
+    text[..len].trim_end_matches('.').len()
+}
+
+/// Разметка начинается только в начале слова.
+fn is_boundary(text: &str, pos: usize) -> bool {
+    text[..pos]
+        .chars()
+        .next_back()
+        .map(|chr| !is_word_char(chr))
+        .unwrap_or(true)
+}
+
+fn is_word_char(chr: char) -> bool {
+    chr.is_alphanumeric() || chr == '_'
+}
+
+fn push_text(nodes: &mut Vec<Node>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+
+    match nodes.last_mut() {
+        Some(Node::Text(last)) => last.push_str(text),
+        _ => nodes.push(Node::Text(text.to_owned())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Node {
+        Node::Text(text.to_owned())
+    }
+
+    fn mention(target: &str, text: &str) -> Node {
+        Node::Mention {
+            target: target.to_owned(),
+            text: text.to_owned(),
+        }
+    }
+
+    fn link(url: &str, text: &str) -> Node {
+        Node::Link {
+            url: url.to_owned(),
+            text: text.to_owned(),
+        }
+    }
+
+    fn hashtag(tag: &str, domain: Option<&str>) -> Node {
+        Node::Hashtag {
+            tag: tag.to_owned(),
+            domain: domain.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn golden_corpus() {
+        let corpus = [
+            ("", vec![]),
+            ("Просто текст", vec![text("Просто текст")]),
+            (
+                "[id1|A] [club2|B]",
+                vec![mention("id1", "A"), text(" "), mention("club2", "B")],
+            ),
+            ("@id123 (Name)", vec![mention("id123", "Name")]),
+            ("*club1 (Name)", vec![mention("club1", "Name")]),
+            (
+                "Спасибо, @durov (Павел)!",
+                vec![text("Спасибо, "), mention("durov", "Павел"), text("!")],
+            ),
+            ("@id123", vec![text("@id123")]),
+            (
+                "mail@example.com (почта)",
+                vec![text("mail@example.com (почта)")],
+            ),
+            (
+                "[https://example.com|text]",
+                vec![link("https://example.com", "text")],
+            ),
+            ("[vk.com/x|text]", vec![link("https://vk.com/x", "text")]),
+            (
+                "[https://vk.com/away.php?to=https%3A%2F%2Fexample.com%2Fpage&cc_key=|text]",
+                vec![link("https://example.com/page", "text")],
+            ),
+            ("#tag", vec![hashtag("tag", None)]),
+            ("#тег", vec![hashtag("тег", None)]),
+            ("#tag@public", vec![hashtag("tag", Some("public"))]),
+            ("#123", vec![text("#123")]),
+            ("a#tag", vec![text("a#tag")]),
+            (
+                "#tag@public.",
+                vec![hashtag("tag", Some("public")), text(".")],
+            ),
+            ("@id1 (A).", vec![mention("id1", "A"), text(".")]),
+            ("@id1. (A)", vec![text("@id1. (A)")]),
+            ("[id1.|A]", vec![text("[id1.|A]")]),
+            ("[id1|A", vec![text("[id1|A")]),
+            ("id1|A]", vec![text("id1|A]")]),
+            ("[id1|]", vec![text("[id1|]")]),
+            ("[|A]", vec![text("[|A]")]),
+            ("[id1 A]", vec![text("[id1 A]")]),
+            ("[[id1|A]]", vec![text("["), mention("id1", "A"), text("]")]),
+            (
+                "[id1|[club2|B]]",
+                vec![text("[id1|"), mention("club2", "B"), text("]")],
+            ),
+            ("[id1|A\nB]", vec![text("[id1|A\nB]")]),
+            ("@id1 (A (B))", vec![text("@id1 (A (B))")]),
+        ];
+
+        for (input, expected) in corpus {
+            assert_eq!(parse(input), expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn unwraps_away_links() {
+        assert_eq!(
+            unwrap_away_link("https://vk.com/away.php?to=https%3A%2F%2Fexample.com%2F%3Fa%3D1"),
+            "https://example.com/?a=1"
+        );
+        assert_eq!(
+            unwrap_away_link("https://m.vk.com/away.php?to=https%3A%2F%2Fexample.com"),
+            "https://example.com"
+        );
+        assert_eq!(
+            unwrap_away_link("https://vk.com/away.php"),
+            "https://vk.com/away.php"
+        );
+        assert_eq!(
+            unwrap_away_link("https://example.com/away.php?to=https%3A%2F%2Fvk.com"),
+            "https://example.com/away.php?to=https%3A%2F%2Fvk.com"
+        );
+        assert_eq!(unwrap_away_link("not a url"), "not a url");
+    }
+}
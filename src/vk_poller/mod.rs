@@ -1,23 +1,45 @@
+mod batcher;
 mod converter;
+mod markup;
 mod poller;
+mod resolver;
 
 use crate::{
     config::Config,
     db::Db,
-    domain::{ChannelEntryId, ChannelInfo},
+    domain::{
+        ChannelEntryId, ChannelInfo, ChannelSettings, TelegramChannelId, VkOwnerId, VkPostId,
+        VkWallAddress,
+    },
+    vk_api,
 };
+use batcher::WallBatcher;
 use poller::VkPoller;
-use std::{collections::HashMap, sync::Arc};
-use tokio::sync::Mutex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+use teloxide::types::{MessageId, UserId};
+use tokio::sync::{watch, Mutex};
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
 
+pub use resolver::WallCheck;
+
+/// Управление запущенным опросчиком канала.
+struct PollerHandle {
+    stop_token: CancellationToken,
+    settings: watch::Sender<ChannelSettings>,
+}
+
 #[derive(Clone)]
 pub struct VkPollManager {
-    config: Arc<Config>,
+    vk_client: Arc<vk_api::Client>,
+    wall_batcher: WallBatcher,
     db: Db,
     bot: teloxide::Bot,
     tracker: TaskTracker,
-    stop_tokens: Arc<Mutex<HashMap<ChannelEntryId, CancellationToken>>>,
+    pollers: Arc<Mutex<HashMap<ChannelEntryId, PollerHandle>>>,
     cancellation_token: CancellationToken,
 }
 
@@ -30,25 +52,84 @@ impl VkPollManager {
         tracker: TaskTracker,
         token: CancellationToken,
     ) -> Self {
+        let vk_client = vk_api::Client::new(
+            &config.vk.service_key,
+            &config.vk.language,
+            vk_api::ClientRateLimit {
+                requests_per_second: config.vk.rate_limit.requests_per_second,
+                burst: config.vk.rate_limit.burst,
+            },
+            vk_api::ClientRetries {
+                max_attempts: config.vk.retries.max_attempts,
+                initial_delay: Duration::from_millis(config.vk.retries.initial_delay_ms),
+                max_delay: Duration::from_millis(config.vk.retries.max_delay_ms),
+            },
+            config.vk.debug.as_ref().map(|debug| vk_api::ClientDebug {
+                save_responses: debug.save_responses,
+                responses_dir_path: debug.responses_dir_path.to_owned(),
+            }),
+        );
+
+        let vk_client = Arc::new(vk_client);
+        let wall_batcher = WallBatcher::new(vk_client.clone(), tracker.clone(), token.clone());
+
         Self {
-            config,
+            vk_client,
+            wall_batcher,
             db,
             bot,
             tracker,
-            stop_tokens: Default::default(),
+            pollers: Default::default(),
             cancellation_token: token,
         }
     }
 
     pub async fn run(self) {
-        for (id, info) in self.db.get_channels().await {
+        for (id, info) in self.db.get_channels(None).await {
             self.spawn_poller(id, info).await;
         }
     }
 
     /// Возвращает список пар идентификаторов стен Vk и Telegram каналов.
-    pub async fn get_channels(&self) -> HashMap<ChannelEntryId, ChannelInfo> {
-        self.db.get_channels().await.into_iter().collect()
+    ///
+    /// Если указан `owner`, возвращаются только каналы, добавленные этим пользователем.
+    /// Каналы упорядочены по идентификатору, чтобы нумерация в списках была стабильной.
+    pub async fn get_channels(
+        &self,
+        owner: Option<UserId>,
+    ) -> BTreeMap<ChannelEntryId, ChannelInfo> {
+        self.db.get_channels(owner).await.into_iter().collect()
+    }
+
+    /// Ищет публикацию ВК, из которой получено сообщение в канале.
+    ///
+    /// Если указан владелец, поиск ведётся только среди его каналов.
+    pub async fn find_post_source(
+        &self,
+        tg_channel: TelegramChannelId,
+        message_id: MessageId,
+        owner: Option<UserId>,
+    ) -> Option<VkPostId> {
+        let (id, post) = self.db.find_forwarded_post(tg_channel, message_id).await?;
+
+        if owner.is_some() && !self.get_channels(owner).await.contains_key(&id) {
+            return None;
+        }
+
+        Some(post)
+    }
+
+    /// Определяет идентификатор владельца стены по её адресу.
+    ///
+    /// Возвращает `None`, если такой стены не существует.
+    pub async fn resolve_wall(&self, address: &VkWallAddress) -> anyhow::Result<Option<VkOwnerId>> {
+        resolver::resolve_owner_id(&self.vk_client, address).await
+    }
+
+    /// Проверяет, что публикации со стены можно читать,
+    /// и возвращает информацию о её владельце.
+    pub async fn check_wall(&self, owner_id: VkOwnerId) -> anyhow::Result<WallCheck> {
+        resolver::check_wall(&self.vk_client, owner_id).await
     }
 
     /// Сохраняет канал и запускает для него процесс опроса.
@@ -57,31 +138,54 @@ impl VkPollManager {
         self.spawn_poller(id, info).await;
     }
 
+    /// Сохраняет настройки канала и передаёт их запущенному опросчику.
+    pub async fn update_settings(&self, id: ChannelEntryId, settings: ChannelSettings) -> bool {
+        let pollers = self.pollers.lock().await;
+
+        let Some(poller) = pollers.get(&id) else {
+            return false;
+        };
+
+        self.db.update_channel_settings(id, &settings).await;
+        poller.settings.send_replace(settings);
+
+        true
+    }
+
     async fn spawn_poller(&self, id: ChannelEntryId, info: ChannelInfo) {
         let stop_token = CancellationToken::new();
+        let (settings, settings_receiver) = watch::channel(info.settings.clone());
 
         self.tracker.spawn(
             VkPoller::new(
-                self.config.clone(),
+                self.vk_client.clone(),
+                self.wall_batcher.clone(),
                 self.db.clone(),
                 id,
                 info,
                 self.bot.clone(),
                 self.cancellation_token.clone(),
                 stop_token.clone(),
+                settings_receiver,
             )
             .run(),
         );
 
-        self.stop_tokens.lock().await.insert(id, stop_token);
+        self.pollers.lock().await.insert(
+            id,
+            PollerHandle {
+                stop_token,
+                settings,
+            },
+        );
     }
 
     pub async fn delete(&self, id: ChannelEntryId) -> bool {
-        let Some(stop_token) = self.stop_tokens.lock().await.remove(&id) else {
+        let Some(poller) = self.pollers.lock().await.remove(&id) else {
             return false;
         };
 
-        stop_token.cancel();
+        poller.stop_token.cancel();
 
         self.db.remove_channel(id).await;
 
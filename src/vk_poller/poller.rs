@@ -1,52 +1,71 @@
-use super::converter;
+use super::{batcher::WallBatcher, converter, resolver};
 use crate::{
-    config, db,
-    domain::{ChannelEntryId, ChannelInfo, TelegramPost},
+    db,
+    domain::{
+        ChannelEntryId, ChannelInfo, ChannelSettings, DeletionMode, ForwardedMessage,
+        PartialPostPolicy, PostLayout, TelegramPost, VkOwnerId, VkPostId, VkWallAddress,
+    },
     vk_api,
 };
 use anyhow::Context;
 use chrono::Utc;
 use std::{sync::Arc, time::Duration};
-use tokio::time::sleep;
+use tokio::{sync::watch, time::sleep};
 use tokio_util::sync::CancellationToken;
 
+/// Пауза в опросе после превышения лимита запросов к ВК.
+const TOO_MANY_REQUESTS_PAUSE: chrono::Duration = chrono::Duration::minutes(1);
+
+/// Пауза в опросе, если ВК отверг ключ доступа из конфига.
+const INVALID_TOKEN_PAUSE: chrono::Duration = chrono::Duration::minutes(10);
+
+/// Пауза в опросе закрытой, удалённой или заблокированной стены.
+const UNAVAILABLE_WALL_PAUSE: chrono::Duration = chrono::Duration::hours(1);
+
+/// Интервал проверки недавних публикаций на правки и удаление.
+const TRACKED_POSTS_CHECK_INTERVAL: chrono::Duration = chrono::Duration::minutes(5);
+
 pub struct VkPoller {
     db: db::Db,
     id: ChannelEntryId,
     info: ChannelInfo,
     bot: teloxide::Bot,
-    vk_client: vk_api::Client,
+    vk_client: Arc<vk_api::Client>,
+    wall_batcher: WallBatcher,
     cancellation_token: CancellationToken,
     stop_token: CancellationToken,
+    settings: watch::Receiver<ChannelSettings>,
+    suspended_until: Option<chrono::DateTime<Utc>>,
+    last_tracking_check: Option<chrono::DateTime<Utc>>,
+    source_names: converter::SourceNames,
 }
 
 impl VkPoller {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        config: Arc<config::Config>,
+        vk_client: Arc<vk_api::Client>,
+        wall_batcher: WallBatcher,
         db: db::Db,
         id: ChannelEntryId,
         info: ChannelInfo,
         bot: teloxide::Bot,
         cancellation_token: CancellationToken,
         stop_token: CancellationToken,
+        settings: watch::Receiver<ChannelSettings>,
     ) -> Self {
-        let vk_client = vk_api::Client::new(
-            &config.vk.service_key,
-            &config.vk.language,
-            config.vk.debug.as_ref().map(|debug| vk_api::ClientDebug {
-                save_responses: debug.save_responses,
-                responses_dir_path: debug.responses_dir_path.to_owned(),
-            }),
-        );
-
         Self {
             db,
             id,
             info,
             bot,
             vk_client,
+            wall_batcher,
             cancellation_token,
             stop_token,
+            settings,
+            suspended_until: None,
+            last_tracking_check: None,
+            source_names: converter::SourceNames::new(),
         }
     }
 
@@ -58,7 +77,12 @@ impl VkPoller {
                 .map(|dt| self.info.poll_interval < (chrono::Utc::now() - dt))
                 .unwrap_or(true);
 
-            if !should_poll {
+            let is_suspended = self
+                .suspended_until
+                .map(|dt| chrono::Utc::now() < dt)
+                .unwrap_or(false);
+
+            if !should_poll || is_suspended {
                 tokio::select! {
                     _ = self.cancellation_token.cancelled() => { break; },
                     _ = self.stop_token.cancelled() => { break; },
@@ -68,10 +92,14 @@ impl VkPoller {
 
             log::debug!("Time to poll VK wall '{}'...", self.info.vk_public_id.0);
 
-            if let Some(last_post_datetime) = self.info.last_post_datetime {
-                self.poll_new_posts(last_post_datetime).await;
-            } else {
-                self.first_poll().await;
+            if let Some(owner_id) = self.owner_id().await {
+                if self.info.last_post_id.is_some() || self.info.last_post_datetime.is_some() {
+                    self.poll_new_posts(owner_id).await;
+                    self.check_tracked_posts().await;
+                    self.close_expired_polls().await;
+                } else {
+                    self.first_poll(owner_id).await;
+                }
             }
 
             self.info.last_poll_datetime = Some(Utc::now());
@@ -83,15 +111,62 @@ impl VkPoller {
         }
     }
 
-    async fn poll_new_posts(&mut self, last_post_datetime: chrono::DateTime<chrono::Utc>) {
-        let posts = match self.get_new_posts(last_post_datetime).await {
-            Ok(posts) => posts,
-            Err(err) => {
-                return log::warn!(
-                    "Failed to fetch new posts from VK wall '{id}': {err:#}",
-                    id = self.info.vk_public_id.0
+    /// Возвращает идентификатор владельца стены.
+    ///
+    /// Каналы, добавленные до появления идентификатора, хранят только короткий адрес,
+    /// поэтому для них идентификатор определяется и сохраняется при первом опросе.
+    async fn owner_id(&mut self) -> Option<VkOwnerId> {
+        if let Some(owner_id) = self.info.vk_owner_id {
+            return Some(owner_id);
+        }
+
+        let id = &self.info.vk_public_id.0;
+
+        let Some(address) = VkWallAddress::parse(id) else {
+            log::error!(
+                "Invalid VK wall address '{id}', polling paused for {secs} seconds",
+                secs = UNAVAILABLE_WALL_PAUSE.num_seconds(),
+            );
+
+            self.suspended_until = Some(Utc::now() + UNAVAILABLE_WALL_PAUSE);
+            return None;
+        };
+
+        match resolver::resolve_owner_id(&self.vk_client, &address).await {
+            Ok(Some(owner_id)) => {
+                log::info!("VK wall '{id}' resolved to owner id {}", owner_id.0);
+
+                self.info.vk_owner_id = Some(owner_id);
+                self.db.update_channel(self.id, &self.info).await;
+
+                Some(owner_id)
+            }
+            Ok(None) => {
+                log::error!(
+                    "VK wall '{id}' doesn't exist, polling paused for {secs} seconds",
+                    secs = UNAVAILABLE_WALL_PAUSE.num_seconds(),
                 );
+
+                self.suspended_until = Some(Utc::now() + UNAVAILABLE_WALL_PAUSE);
+                None
+            }
+            Err(err) => {
+                self.handle_fetch_error("resolve owner id", err);
+                None
             }
+        }
+    }
+
+    async fn poll_new_posts(&mut self, owner_id: VkOwnerId) {
+        // Пока прерванная публикация не дослана, следующие не отправляются,
+        // чтобы не нарушить порядок постов в канале.
+        if !self.resume_unfinished_post().await {
+            return;
+        }
+
+        let posts = match self.get_new_posts(owner_id).await {
+            Ok(posts) => posts,
+            Err(err) => return self.handle_fetch_error("fetch new posts", err),
         };
 
         if posts.is_empty() {
@@ -99,44 +174,407 @@ impl VkPoller {
         }
 
         for post in posts.into_iter().rev() {
-            let post_id = post.id.0;
-            let post_datetime = post.date;
+            if !self.forward_post(owner_id, post).await {
+                break;
+            }
+        }
+    }
+
+    /// Пересылает новую публикацию в канал. Возвращает `true`, если она отправлена целиком.
+    async fn forward_post(&mut self, owner_id: VkOwnerId, post: vk_api::Post) -> bool {
+        let post_id = VkPostId {
+            owner_id,
+            post_id: post.id.0,
+        };
+        let post_datetime = post.date;
+        let content_hash = converter::content_hash(&post);
+        let layout = self.settings.borrow().post_layout;
 
-            let post = match self.convert_vk_to_tg(post).await {
-                Ok(post) => post,
+        let tg_post = match self.convert_vk_to_tg(post, layout).await {
+            Ok(post) => post,
+            Err(err) => {
+                log::warn!("Failed to convert VK post #{}: {err:#}", post_id.post_id);
+                return false;
+            }
+        };
+
+        self.db
+            .start_forwarded_post(self.id, post_id, post_datetime, content_hash, layout)
+            .await;
+
+        self.send_post_steps(post_id, post_datetime, tg_post, 0, Vec::new())
+            .await
+    }
+
+    /// Досылает публикацию, отправка которой была прервана, в том числе до перезапуска.
+    /// Возвращает `true`, если прерванных публикаций не осталось.
+    async fn resume_unfinished_post(&mut self) -> bool {
+        let Some(unfinished) = self.db.get_unfinished_post(self.id).await else {
+            return true;
+        };
+
+        let post_id = unfinished.post;
+        let policy = self.settings.borrow().partial_post_policy;
+
+        log::info!(
+            "Resuming partially sent post #{id} after {steps} steps with {policy:?} policy",
+            id = post_id.post_id,
+            steps = unfinished.sent_steps,
+        );
+
+        // Содержимое фотографий не хранится, поэтому публикацию нужно получить заново.
+        let post = match self.vk_client.get_post(post_id).await {
+            Ok(Some(post)) => post,
+            Ok(None) => {
+                log::warn!("Partially sent post {post_id} is no longer available on VK, skipping");
+
+                self.db.finish_forwarded_post(self.id, post_id).await;
+                self.mark_post_forwarded(post_id, unfinished.post_datetime)
+                    .await;
+
+                return true;
+            }
+            Err(err) => {
+                self.handle_fetch_error("fetch partially sent post", err);
+                return false;
+            }
+        };
+
+        let tg_post = match self.convert_vk_to_tg(post, unfinished.layout).await {
+            Ok(post) => post,
+            Err(err) => {
+                log::warn!("Failed to convert VK post #{}: {err:#}", post_id.post_id);
+                return false;
+            }
+        };
+
+        let sent = self.db.get_forwarded_messages(self.id, post_id).await;
+
+        match policy {
+            PartialPostPolicy::Complete => {
+                self.send_post_steps(
+                    post_id,
+                    unfinished.post_datetime,
+                    tg_post,
+                    unfinished.sent_steps,
+                    sent,
+                )
+                .await
+            }
+            PartialPostPolicy::Restart => {
+                let (deleted, error) =
+                    crate::bot::delete_messages(&self.bot, self.info.tg_channel, &sent).await;
+
+                if let Some(err) = error {
+                    // Удалённые сообщения забываются, остальные будут удалены при следующей попытке.
+                    self.db
+                        .forget_forwarded_messages(self.id, post_id, &deleted)
+                        .await;
+
+                    log::warn!(
+                        "Failed to delete partially sent post #{}: {err:#}",
+                        post_id.post_id
+                    );
+
+                    return false;
+                }
+
+                self.db.reset_forwarded_post(self.id, post_id).await;
+
+                self.send_post_steps(post_id, unfinished.post_datetime, tg_post, 0, Vec::new())
+                    .await
+            }
+        }
+    }
+
+    /// Выполняет шаги отправки публикации, начиная с `sent_steps`, сохраняя прогресс после каждого.
+    /// Возвращает `true`, если публикация отправлена целиком.
+    async fn send_post_steps(
+        &mut self,
+        post_id: VkPostId,
+        post_datetime: chrono::DateTime<chrono::Utc>,
+        post: TelegramPost,
+        sent_steps: usize,
+        mut sent: Vec<ForwardedMessage>,
+    ) -> bool {
+        let channel_id = post.channel_id;
+        let steps = crate::bot::post_steps(post);
+
+        for (n, step) in steps.into_iter().enumerate().skip(sent_steps) {
+            let poll_close_datetime = match &step {
+                crate::bot::PostStep::Poll(poll) if !poll.is_closed => poll.close_datetime,
+                _ => None,
+            };
+
+            match crate::bot::send_step(&self.bot, channel_id, step, &sent).await {
+                Ok(message_ids) => {
+                    self.db
+                        .add_forwarded_step(self.id, channel_id, post_id, n + 1, &message_ids)
+                        .await;
+
+                    if let (Some(close_datetime), Some(message)) =
+                        (poll_close_datetime, message_ids.first())
+                    {
+                        self.db
+                            .schedule_poll_close(
+                                channel_id,
+                                message.message_id,
+                                Some(close_datetime),
+                            )
+                            .await;
+                    }
+
+                    sent.extend(message_ids);
+                }
                 Err(err) => {
-                    log::warn!("Failed to convert VK post #{post_id}: {err:#}");
-                    break;
+                    log::warn!(
+                        "Failed to send post #{id} to the Telegram at step {step}: {err:#}",
+                        id = post_id.post_id,
+                        step = n + 1,
+                    );
+                    return false;
                 }
+            }
+        }
+
+        log::info!(
+            "Successfully send post #{} to the Telegram",
+            post_id.post_id
+        );
+
+        self.db.finish_forwarded_post(self.id, post_id).await;
+        self.mark_post_forwarded(post_id, post_datetime).await;
+
+        true
+    }
+
+    /// Периодически переносит в канал правки и удаление недавних публикаций.
+    ///
+    /// Публикации, вышедшие раньше окна из настроек канала, не проверяются.
+    async fn check_tracked_posts(&mut self) {
+        let edit_window = self.settings.borrow().edit_window;
+        let deletion_mode = self.settings.borrow().deletion_mode;
+
+        if edit_window <= chrono::Duration::zero() {
+            return;
+        }
+
+        let should_check = self
+            .last_tracking_check
+            .map(|dt| TRACKED_POSTS_CHECK_INTERVAL < (Utc::now() - dt))
+            .unwrap_or(true);
+
+        if !should_check {
+            return;
+        }
+
+        self.last_tracking_check = Some(Utc::now());
+
+        let tracked = self
+            .db
+            .get_tracked_posts(self.id, Utc::now() - edit_window)
+            .await;
+
+        for chunk in tracked.chunks(vk_api::MAX_POSTS_BY_ID) {
+            let ids = chunk.iter().map(|tracked| tracked.post).collect::<Vec<_>>();
+
+            let posts = match self.vk_client.get_posts(&ids).await {
+                Ok(posts) => posts,
+                Err(err) => return self.handle_fetch_error("fetch recent posts", err),
             };
 
-            match crate::bot::send_post(&self.bot, post).await {
-                Ok(()) => {
-                    log::info!("Successfully send post #{post_id} to the Telegram");
+            if deletion_mode != DeletionMode::Off {
+                for tracked in chunk {
+                    let is_returned = posts.iter().any(|post| {
+                        post.owner_id == tracked.post.owner_id.0
+                            && post.id.0 == tracked.post.post_id
+                    });
+
+                    if !is_returned {
+                        self.check_deleted_post(tracked.post, deletion_mode).await;
+                    }
+                }
+            }
+
+            for post in posts {
+                let post_id = VkPostId {
+                    owner_id: VkOwnerId(post.owner_id),
+                    post_id: post.id.0,
+                };
 
-                    self.info.last_post_datetime = Some(post_datetime);
-                    self.db.update_channel(self.id, &self.info).await;
+                let Some(tracked) = chunk.iter().find(|tracked| tracked.post == post_id) else {
+                    continue;
+                };
+
+                let content_hash = converter::content_hash(&post);
+
+                match &tracked.content_hash {
+                    Some(old_hash) if *old_hash == content_hash => {}
+                    // Публикация переслана до появления синхронизации правок,
+                    // её текущее содержимое считается исходным.
+                    None => {
+                        self.db
+                            .set_content_hash(self.id, post_id, content_hash)
+                            .await
+                    }
+                    Some(_) => {
+                        self.sync_post_edit(post_id, post, content_hash, tracked.layout)
+                            .await
+                    }
+                }
+            }
+        }
+    }
+
+    async fn sync_post_edit(
+        &mut self,
+        post_id: VkPostId,
+        post: vk_api::Post,
+        content_hash: String,
+        layout: PostLayout,
+    ) {
+        let id = post_id.post_id;
+
+        let tg_post = match self.convert_vk_to_tg(post, layout).await {
+            Ok(post) => post,
+            Err(err) => return log::warn!("Failed to convert edited VK post #{id}: {err:#}"),
+        };
+
+        let messages = self.db.get_forwarded_messages(self.id, post_id).await;
+
+        match crate::bot::edit_post(&self.bot, tg_post, &messages).await {
+            Ok(Some(edited)) => {
+                log::info!("Successfully synced edit of post #{id} to the Telegram");
+
+                self.db
+                    .update_forwarded_messages(self.info.tg_channel, &edited)
+                    .await;
+            }
+            Ok(None) => {
+                log::warn!(
+                    "Edited post #{id} doesn't fit into already sent messages, edit is skipped"
+                );
+            }
+            Err(err) => {
+                return log::warn!("Failed to sync edit of post #{id} to the Telegram: {err:#}");
+            }
+        }
+
+        self.db
+            .set_content_hash(self.id, post_id, content_hash)
+            .await;
+    }
+
+    /// Переносит в канал удаление публикации, если ВКонтакте подтверждает его.
+    async fn check_deleted_post(&mut self, post_id: VkPostId, deletion_mode: DeletionMode) {
+        let id = post_id.post_id;
+
+        // Пакетный запрос мог не вернуть публикацию и по другой причине,
+        // поэтому перед удалением сообщений она запрашивается отдельно.
+        match self.vk_client.get_post(post_id).await {
+            Ok(None) => {}
+            Ok(Some(_)) => return,
+            Err(err) => return self.handle_fetch_error("check deleted post", err),
+        }
+
+        let messages = self.db.get_forwarded_messages(self.id, post_id).await;
+        let channel_id = self.info.tg_channel;
+
+        let result = match deletion_mode {
+            DeletionMode::Off => return,
+            DeletionMode::Delete => {
+                match crate::bot::delete_messages(&self.bot, channel_id, &messages).await {
+                    (_, None) => Ok(true),
+                    (deleted, Some(err)) => {
+                        self.db
+                            .forget_forwarded_messages(self.id, post_id, &deleted)
+                            .await;
+
+                        // Telegram не даёт удалять сообщения старше 48 часов,
+                        // поэтому оставшиеся сообщения хотя бы помечаются.
+                        log::warn!(
+                            "Failed to delete post #{id} from the Telegram, marking it instead: {err:#}"
+                        );
+
+                        let remaining = messages
+                            .iter()
+                            .filter(|message| !deleted.contains(&message.message_id))
+                            .cloned()
+                            .collect::<Vec<_>>();
+
+                        crate::bot::mark_post_deleted(&self.bot, channel_id, &remaining).await
+                    }
+                }
+            }
+            DeletionMode::Mark => {
+                crate::bot::mark_post_deleted(&self.bot, channel_id, &messages).await
+            }
+        };
+
+        match result {
+            Ok(true) => {
+                log::info!("Post #{id} was deleted on VK, mirrored with {deletion_mode:?} mode");
+            }
+            Ok(false) => {
+                log::warn!(
+                    "Post #{id} was deleted on VK, but its original text can't be kept, not marked"
+                );
+            }
+            Err(err) => {
+                return log::warn!(
+                    "Failed to mirror deletion of post #{id} to the Telegram: {err:#}"
+                );
+            }
+        }
+
+        self.db.mark_post_deleted(self.id, post_id).await;
+    }
+
+    /// Закрывает опросы, завершившиеся во ВКонтакте.
+    async fn close_expired_polls(&mut self) {
+        let channel_id = self.info.tg_channel;
+
+        for message_id in self.db.get_expired_polls(channel_id, Utc::now()).await {
+            match crate::bot::stop_poll(&self.bot, channel_id, message_id).await {
+                Ok(()) => {
+                    self.db
+                        .schedule_poll_close(channel_id, message_id, None)
+                        .await;
                 }
                 Err(err) => {
-                    log::warn!("Failed to send post #{post_id} to the Telegram: {err:#}");
-                    break;
+                    log::warn!("Failed to close poll {message_id} in the Telegram: {err:#}");
                 }
             }
         }
     }
 
-    async fn get_new_posts(
+    /// Сдвигает отметку последней пересланной публикации.
+    async fn mark_post_forwarded(
         &mut self,
-        last_post_datetime: chrono::DateTime<chrono::Utc>,
-    ) -> anyhow::Result<Vec<vk_api::Post>> {
+        post_id: VkPostId,
+        post_datetime: chrono::DateTime<chrono::Utc>,
+    ) {
+        self.info.last_post_id = self.info.last_post_id.max(Some(post_id.post_id));
+        self.info.last_post_datetime = self.info.last_post_datetime.max(Some(post_datetime));
+        self.db.update_channel(self.id, &self.info).await;
+    }
+
+    /// Возвращает ещё не пересланные публикации, от новых к старым.
+    ///
+    /// Идентификаторы публикаций на стене только растут, поэтому новой считается
+    /// публикация с идентификатором больше последнего пересланного. В отличие от
+    /// времени публикации, он не меняется при редактировании и не совпадает
+    /// у постов, вышедших в одну секунду. Для каналов, у которых идентификатор
+    /// ещё не сохранён, используется время публикации.
+    async fn get_new_posts(&mut self, owner_id: VkOwnerId) -> anyhow::Result<Vec<vk_api::Post>> {
         let mut offset = 0;
         let count = 5;
 
         let mut new_posts = Vec::<vk_api::Post>::new();
         'fetch: loop {
             let posts = self
-                .vk_client
-                .get_posts_from_wall(&self.info.vk_public_id, offset, count)
+                .wall_batcher
+                .get_posts_from_wall(owner_id, offset, count)
                 .await
                 .context("fetching posts from VK")?;
 
@@ -147,14 +585,23 @@ impl VkPoller {
             }
 
             for post in posts {
-                if post.is_pinned() && post.date <= last_post_datetime {
-                    continue;
-                }
+                if self.is_old_post(&post) {
+                    if post.is_pinned() {
+                        continue;
+                    }
 
-                if post.date <= last_post_datetime {
                     break 'fetch;
                 }
 
+                if self
+                    .db
+                    .is_post_forwarded(self.id, owner_id, post.id.0)
+                    .await
+                {
+                    log::debug!("Post #{} was already forwarded, skipping", post.id.0);
+                    continue;
+                }
+
                 new_posts.push(post);
             }
 
@@ -162,46 +609,139 @@ impl VkPoller {
         }
 
         // Первыми в списке идут новые закреплённые посты.
-        // Чтобы не нарушить хронологический порядок отправки, сортируем по дате.
-        new_posts.sort_by(|a, b| b.date.cmp(&a.date));
+        // Чтобы не нарушить порядок отправки, сортируем по идентификатору.
+        new_posts.sort_by_key(|post| std::cmp::Reverse(post.id.0));
 
         Ok(new_posts)
     }
 
-    async fn convert_vk_to_tg(&self, post: vk_api::Post) -> anyhow::Result<TelegramPost> {
-        converter::vk_to_tg(self.info.tg_channel, post)
+    /// Проверяет, что публикация вышла не позже последней пересланной.
+    fn is_old_post(&self, post: &vk_api::Post) -> bool {
+        match (self.info.last_post_id, self.info.last_post_datetime) {
+            (Some(last_post_id), _) => post.id.0 <= last_post_id,
+            (None, Some(last_post_datetime)) => post.date <= last_post_datetime,
+            (None, None) => false,
+        }
+    }
+
+    async fn convert_vk_to_tg(
+        &mut self,
+        post: vk_api::Post,
+        layout: PostLayout,
+    ) -> anyhow::Result<TelegramPost> {
+        let sources = self.source_names(&post).await;
+
+        converter::vk_to_tg(self.info.tg_channel, post, &sources, layout)
             .await
             .context("converting vk post to telegram format")
     }
 
-    async fn first_poll(&mut self) {
+    /// Возвращает названия стен, с которых сделаны репосты в публикации.
+    ///
+    /// Названия кэшируются на всё время работы опросчика. Если название получить
+    /// не удалось, репост подписывается без него.
+    async fn source_names(&mut self, post: &vk_api::Post) -> converter::SourceNames {
+        let mut sources = converter::SourceNames::new();
+
+        for owner_id in converter::repost_owner_ids(post) {
+            if let Some(name) = self.source_names.get(&owner_id) {
+                sources.insert(owner_id, name.clone());
+                continue;
+            }
+
+            match resolver::resolve_owner_name(&self.vk_client, VkOwnerId(owner_id)).await {
+                Ok(Some(name)) => {
+                    self.source_names.insert(owner_id, name.clone());
+                    sources.insert(owner_id, name);
+                }
+                Ok(None) => {}
+                Err(err) => log::warn!("Failed to get name of VK wall {owner_id}: {err:#}"),
+            }
+        }
+
+        sources
+    }
+
+    async fn first_poll(&mut self, owner_id: VkOwnerId) {
         let id = &self.info.vk_public_id.0;
 
-        match self.get_first_non_pinned_post_id().await {
+        match self.get_first_non_pinned_post_id(owner_id).await {
             Ok(Some(post)) => {
                 let post_id = post.id.0;
                 log::debug!("Successfully fetch non pinned post {post_id} from VK wall '{id}'");
 
+                self.info.last_post_id = Some(post_id);
                 self.info.last_post_datetime = Some(post.date);
                 self.db.update_channel(self.id, &self.info).await;
             }
             Ok(None) => {
                 log::info!("No posts on VK wall '{id}'");
             }
-            Err(err) => {
-                log::warn!("Failed to fetch latest post from VK wall '{id}': {err:#}");
-            }
+            Err(err) => self.handle_fetch_error("fetch latest post", err),
         }
     }
 
-    async fn get_first_non_pinned_post_id(&self) -> anyhow::Result<Option<vk_api::Post>> {
+    /// Логирует ошибку получения постов и, в зависимости от её типа,
+    /// приостанавливает опрос стены.
+    fn handle_fetch_error(&mut self, action: &str, err: anyhow::Error) {
+        let id = &self.info.vk_public_id.0;
+
+        let pause = match err.downcast_ref::<vk_api::VkError>() {
+            Some(vk_api::VkError::TooManyRequests(_)) => {
+                log::warn!(
+                    "Failed to {action} from VK wall '{id}', too many requests, \
+                     polling paused for {secs} seconds: {err:#}",
+                    secs = TOO_MANY_REQUESTS_PAUSE.num_seconds(),
+                );
+
+                TOO_MANY_REQUESTS_PAUSE
+            }
+            Some(vk_api::VkError::InvalidToken(_)) => {
+                log::error!(
+                    "Failed to {action} from VK wall '{id}', VK rejected service key, \
+                     check config, polling paused for {secs} seconds: {err:#}",
+                    secs = INVALID_TOKEN_PAUSE.num_seconds(),
+                );
+
+                INVALID_TOKEN_PAUSE
+            }
+            Some(vk_api::VkError::AccessDenied(_)) => {
+                log::error!(
+                    "Failed to {action} from VK wall '{id}', wall is private or access denied, \
+                     polling paused for {secs} seconds: {err:#}",
+                    secs = UNAVAILABLE_WALL_PAUSE.num_seconds(),
+                );
+
+                UNAVAILABLE_WALL_PAUSE
+            }
+            Some(vk_api::VkError::GroupBlocked(_)) => {
+                log::error!(
+                    "Failed to {action} from VK wall '{id}', community is blocked or deleted, \
+                     polling paused for {secs} seconds: {err:#}",
+                    secs = UNAVAILABLE_WALL_PAUSE.num_seconds(),
+                );
+
+                UNAVAILABLE_WALL_PAUSE
+            }
+            Some(vk_api::VkError::Other(_)) | None => {
+                return log::warn!("Failed to {action} from VK wall '{id}': {err:#}");
+            }
+        };
+
+        self.suspended_until = Some(Utc::now() + pause);
+    }
+
+    async fn get_first_non_pinned_post_id(
+        &self,
+        owner_id: VkOwnerId,
+    ) -> anyhow::Result<Option<vk_api::Post>> {
         let mut offset = 0;
         let count = 5;
 
         loop {
             let posts = self
-                .vk_client
-                .get_posts_from_wall(&self.info.vk_public_id, offset, count)
+                .wall_batcher
+                .get_posts_from_wall(owner_id, offset, count)
                 .await
                 .context("fetching posts from VK")?;
 
@@ -0,0 +1,160 @@
+use crate::{
+    domain::{VkOwnerId, VkWallAddress},
+    vk_api,
+};
+use anyhow::Context;
+
+/// Определяет идентификатор владельца стены по её адресу.
+///
+/// Возвращает `None`, если по адресу нет ни пользователя, ни сообщества.
+pub async fn resolve_owner_id(
+    vk_client: &vk_api::Client,
+    address: &VkWallAddress,
+) -> anyhow::Result<Option<VkOwnerId>> {
+    let screen_name = match address {
+        VkWallAddress::OwnerId(owner_id) => return Ok(Some(*owner_id)),
+        VkWallAddress::ScreenName(screen_name) => screen_name,
+    };
+
+    let Some(resolved) = vk_client
+        .resolve_screen_name(screen_name)
+        .await
+        .with_context(|| format!("resolving screen name '{screen_name}'"))?
+    else {
+        return Ok(None);
+    };
+
+    Ok(match resolved.r#type {
+        vk_api::ScreenNameType::User => Some(VkOwnerId(resolved.object_id)),
+        vk_api::ScreenNameType::Group => Some(VkOwnerId(-resolved.object_id)),
+        vk_api::ScreenNameType::Application | vk_api::ScreenNameType::Other(_) => None,
+    })
+}
+
+/// Возвращает название сообщества или имя пользователя, которому принадлежит стена.
+///
+/// Возвращает `None`, если ни пользователя, ни сообщества с таким идентификатором нет.
+pub async fn resolve_owner_name(
+    vk_client: &vk_api::Client,
+    owner_id: VkOwnerId,
+) -> anyhow::Result<Option<String>> {
+    if owner_id.0 < 0 {
+        vk_client
+            .get_group_by_id(-owner_id.0)
+            .await
+            .map(|group| group.map(|group| group.name))
+            .context("fetching group info")
+    } else {
+        vk_client
+            .get_user(owner_id.0)
+            .await
+            .map(|user| user.map(|user| user.full_name()))
+            .context("fetching user info")
+    }
+}
+
+/// Результат проверки стены перед добавлением канала.
+#[derive(Clone, Debug)]
+pub enum WallCheck {
+    /// Стена существует и её публикации доступны.
+    Available(WallInfo),
+
+    /// Пользователя или сообщества с таким идентификатором нет.
+    NotFound,
+
+    /// Страница или сообщество удалены.
+    Deleted,
+
+    /// Страница или сообщество заблокированы.
+    Blocked,
+
+    /// Стена закрыта настройками приватности.
+    Private,
+}
+
+/// Информация о владельце стены для подтверждения пользователем.
+#[derive(Clone, Debug)]
+pub struct WallInfo {
+    /// Название сообщества или имя пользователя.
+    pub name: String,
+
+    /// Короткий адрес, если он задан.
+    pub screen_name: Option<String>,
+
+    /// Ссылка на аватарку.
+    pub avatar: Option<url::Url>,
+}
+
+/// Проверяет, что стена существует, не удалена, не заблокирована
+/// и её публикации можно читать сервисным ключом.
+pub async fn check_wall(
+    vk_client: &vk_api::Client,
+    owner_id: VkOwnerId,
+) -> anyhow::Result<WallCheck> {
+    let info = if owner_id.0 < 0 {
+        match vk_client.get_group_by_id(-owner_id.0).await {
+            Ok(Some(group)) => {
+                if let Some(check) = check_deactivated(group.deactivated.as_deref()) {
+                    return Ok(check);
+                }
+
+                if !group.is_open() {
+                    return Ok(WallCheck::Private);
+                }
+
+                WallInfo {
+                    name: group.name,
+                    screen_name: group.screen_name,
+                    avatar: group.photo_200,
+                }
+            }
+            Ok(None) => return Ok(WallCheck::NotFound),
+            Err(err) if is_invalid_id_error(&err) => return Ok(WallCheck::NotFound),
+            Err(err) => return Err(err.context("fetching group info")),
+        }
+    } else {
+        match vk_client.get_user(owner_id.0).await {
+            Ok(Some(user)) => {
+                if let Some(check) = check_deactivated(user.deactivated.as_deref()) {
+                    return Ok(check);
+                }
+
+                if !user.is_accessible() {
+                    return Ok(WallCheck::Private);
+                }
+
+                WallInfo {
+                    name: user.full_name(),
+                    screen_name: user.screen_name,
+                    avatar: user.photo_200,
+                }
+            }
+            Ok(None) => return Ok(WallCheck::NotFound),
+            Err(err) if is_invalid_id_error(&err) => return Ok(WallCheck::NotFound),
+            Err(err) => return Err(err.context("fetching user info")),
+        }
+    };
+
+    // Страница может быть открыта, а стена при этом скрыта.
+    match vk_client.get_posts_from_wall(owner_id, 0, 1).await {
+        Ok(_) => Ok(WallCheck::Available(info)),
+        Err(err) => match err.downcast_ref::<vk_api::VkError>() {
+            Some(vk_api::VkError::AccessDenied(_)) => Ok(WallCheck::Private),
+            Some(vk_api::VkError::GroupBlocked(_)) => Ok(WallCheck::Blocked),
+            _ => Err(err.context("fetching posts from wall")),
+        },
+    }
+}
+
+fn check_deactivated(deactivated: Option<&str>) -> Option<WallCheck> {
+    match deactivated? {
+        "banned" => Some(WallCheck::Blocked),
+        _ => Some(WallCheck::Deleted),
+    }
+}
+
+/// ВК отвечает ошибкой 100 "invalid parameter" на несуществующий идентификатор.
+fn is_invalid_id_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<vk_api::VkError>()
+        .is_some_and(|error| error.api_error().error_code == 100)
+}